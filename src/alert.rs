@@ -0,0 +1,143 @@
+use crate::types::{ScanEntry, ScanResults};
+use crate::webhook;
+use anyhow::{bail, Error};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+/// Findings listed in a chat message before the rest are summarized as "... and N more".
+const MAX_LISTED: usize = 20;
+
+/// Shape of the body POSTed to a webhook.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertFormat {
+    /// The plain JSON summary (always sent).
+    #[default]
+    Json,
+    /// Slack block-kit message.
+    Slack,
+    /// Discord embed.
+    Discord,
+}
+
+impl FromStr for AlertFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(AlertFormat::Json),
+            "slack" => Ok(AlertFormat::Slack),
+            "discord" => Ok(AlertFormat::Discord),
+            other => bail!("unknown alert format: {other} (expected json, slack, or discord)"),
+        }
+    }
+}
+
+/// Build the webhook body for `format`.
+///
+/// Chat formats report the new findings when a baseline was given, else every open port, and
+/// return `None` when that list is empty so quiet scans don't post.
+pub fn render(
+    format: AlertFormat,
+    results: &ScanResults,
+    new_findings: Option<&[ScanEntry]>,
+) -> Option<Value> {
+    if format == AlertFormat::Json {
+        return Some(webhook::summary_payload(results, new_findings));
+    }
+    let findings: Vec<&ScanEntry> = match new_findings {
+        Some(new) => new.iter().collect(),
+        None => results.entries.iter().filter(|e| e.open).collect(),
+    };
+    if findings.is_empty() {
+        return None;
+    }
+    let headline = format!(
+        "lan-scan-rs: {} {}open port{}",
+        findings.len(),
+        if new_findings.is_some() { "new " } else { "" },
+        if findings.len() == 1 { "" } else { "s" }
+    );
+    let body = finding_lines(&findings);
+    let footer = format!(
+        "{} of {} sockets scanned, {} open",
+        results.scanned_done, results.scanned_total, results.open_count
+    );
+    Some(match format {
+        AlertFormat::Slack => json!({
+            "text": headline,
+            "blocks": [
+                { "type": "header", "text": { "type": "plain_text", "text": headline } },
+                { "type": "section", "text": { "type": "mrkdwn", "text": body } },
+                { "type": "context", "elements": [ { "type": "mrkdwn", "text": footer } ] },
+            ],
+        }),
+        AlertFormat::Discord => json!({
+            "embeds": [{
+                "title": headline,
+                "description": body,
+                "color": 0xE67E22,
+                "footer": { "text": footer },
+            }],
+        }),
+        AlertFormat::Json => unreachable!(),
+    })
+}
+
+/// One "• `ip:port` service" line per finding; both apps render backticks as code.
+fn finding_lines(findings: &[&ScanEntry]) -> String {
+    let mut lines: Vec<String> = findings
+        .iter()
+        .take(MAX_LISTED)
+        .map(|e| {
+            let service = e.service.as_deref().unwrap_or("unknown");
+            format!("• `{}:{}` {}", e.ip, e.port, service)
+        })
+        .collect();
+    if findings.len() > MAX_LISTED {
+        lines.push(format!("… and {} more", findings.len() - MAX_LISTED));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(open_ports: &[u16]) -> ScanResults {
+        ScanResults {
+            scanned_total: 10,
+            scanned_done: 10,
+            open_count: open_ports.len() as u64,
+            entries: open_ports
+                .iter()
+                .map(|&port| ScanEntry {
+                    service: Some("ssh".into()),
+                    ..ScanEntry::test_open("10.0.0.1", port)
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn chat_formats_stay_quiet_without_findings() {
+        let quiet = results(&[]);
+        assert!(render(AlertFormat::Slack, &quiet, None).is_none());
+        assert!(render(AlertFormat::Discord, &quiet, None).is_none());
+        assert!(render(AlertFormat::Json, &quiet, None).is_some());
+        // Open ports that are all in the baseline are not news either.
+        assert!(render(AlertFormat::Slack, &results(&[22]), Some(&[])).is_none());
+    }
+
+    #[test]
+    fn slack_and_discord_shapes() {
+        let r = results(&[22]);
+        let slack = render(AlertFormat::Slack, &r, None).unwrap();
+        assert_eq!(slack["blocks"][0]["type"], "header");
+        assert_eq!(slack["blocks"][1]["text"]["text"], "• `10.0.0.1:22` ssh");
+        let discord = render(AlertFormat::Discord, &r, None).unwrap();
+        assert_eq!(discord["embeds"][0]["title"], "lan-scan-rs: 1 open port");
+    }
+}
@@ -0,0 +1,193 @@
+//! AMQP 0-9-1 fingerprinting for port 5672: send the protocol header and read the broker's
+//! `Connection.Start` frame for the `product`/`version` server properties. Nothing is sent after
+//! that first frame, so no connection is actually opened.
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{self, Instant};
+
+pub const AMQP_PORT: u16 = 5672;
+/// `AMQP` followed by protocol id 0 and version 0-9-1.
+const PROTOCOL_HEADER: &[u8; 8] = b"AMQP\x00\x00\x09\x01";
+const FRAME_METHOD: u8 = 1;
+const FRAME_END: u8 = 0xce;
+/// Brokers put a capabilities table and copyright text in server-properties; a few KB is plenty.
+const MAX_FRAME: usize = 16 * 1024;
+
+/// Send the protocol header and describe the broker from its reply: `amqp rabbitmq 3.12.0`.
+/// A broker that doesn't speak 0-9-1 answers with the header it does support instead.
+pub async fn probe(stream: &mut TcpStream, timeout: Duration) -> Option<String> {
+    time::timeout(timeout, stream.write_all(PROTOCOL_HEADER))
+        .await
+        .ok()?
+        .ok()?;
+    let deadline = Instant::now() + timeout;
+    let mut buf = Vec::with_capacity(1024);
+    let mut tmp = [0u8; 1024];
+    loop {
+        if let Some(banner) = parse_reply(&buf) {
+            return banner;
+        }
+        if buf.len() >= MAX_FRAME {
+            return None;
+        }
+        let n = time::timeout_at(deadline, stream.read(&mut tmp))
+            .await
+            .ok()?
+            .ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&tmp[..n]);
+    }
+}
+
+/// `None` while more bytes are needed; `Some(None)` if the reply isn't AMQP.
+fn parse_reply(buf: &[u8]) -> Option<Option<String>> {
+    if buf.starts_with(b"AMQP") {
+        let v = buf.get(5..8)?;
+        return Some(Some(format!("amqp protocol {}-{}-{}", v[0], v[1], v[2])));
+    }
+    if buf.len() < 7 {
+        // Wait for a full frame header unless the first byte already rules AMQP out.
+        let foreign = buf.first().is_some_and(|&b| b != FRAME_METHOD && b != b'A');
+        return foreign.then_some(None);
+    }
+    let size = u32::from_be_bytes([buf[3], buf[4], buf[5], buf[6]]) as usize;
+    if buf[0] != FRAME_METHOD || size > MAX_FRAME {
+        return Some(None);
+    }
+    let frame = buf.get(7..7 + size + 1)?;
+    if frame[size] != FRAME_END {
+        return Some(None);
+    }
+    Some(connection_start(&frame[..size]))
+}
+
+/// Pull product and version out of a `Connection.Start` method payload.
+fn connection_start(payload: &[u8]) -> Option<String> {
+    // class 10 (connection), method 10 (start), then version-major/minor.
+    let (head, rest) = payload.split_at_checked(6)?;
+    if head[..4] != [0, 10, 0, 10] {
+        return None;
+    }
+    let (props, _) = read_long(rest)?;
+    let (mut product, mut version) = (None, None);
+    let mut table = props;
+    while !table.is_empty() {
+        let (name, rest) = read_short(table)?;
+        let (value, rest) = read_field(rest)?;
+        match (name, value) {
+            (b"product", Field::Str(s)) => product = Some(s),
+            (b"version", Field::Str(s)) => version = Some(s),
+            _ => {}
+        }
+        table = rest;
+    }
+    let mut banner = String::from("amqp");
+    for part in [product, version].into_iter().flatten() {
+        banner.push(' ');
+        banner.push_str(&String::from_utf8_lossy(part).to_ascii_lowercase());
+    }
+    Some(banner)
+}
+
+enum Field<'a> {
+    Str(&'a [u8]),
+    Other,
+}
+
+/// One field-table value; `None` on a type we can't size.
+fn read_field(buf: &[u8]) -> Option<(Field<'_>, &[u8])> {
+    let (&kind, rest) = buf.split_first()?;
+    let fixed = match kind {
+        b'S' => {
+            let (s, rest) = read_long(rest)?;
+            return Some((Field::Str(s), rest));
+        }
+        // Nested tables, arrays, and byte arrays are length-prefixed.
+        b'F' | b'A' | b'x' => return read_long(rest).map(|(_, rest)| (Field::Other, rest)),
+        b's' => return read_short(rest).map(|(_, rest)| (Field::Other, rest)),
+        b'V' => 0,
+        b't' | b'b' | b'B' => 1,
+        b'u' | b'U' => 2,
+        b'I' | b'i' | b'f' => 4,
+        b'D' => 5,
+        b'l' | b'L' | b'd' | b'T' => 8,
+        _ => return None,
+    };
+    rest.split_at_checked(fixed)
+        .map(|(_, rest)| (Field::Other, rest))
+}
+
+/// A `shortstr`: one length byte, then the bytes.
+fn read_short(buf: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (&len, rest) = buf.split_first()?;
+    rest.split_at_checked(len as usize)
+}
+
+/// A `longstr` or table: a 32-bit length, then the bytes.
+fn read_long(buf: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (len, rest) = buf.split_at_checked(4)?;
+    let len = u32::from_be_bytes(len.try_into().ok()?) as usize;
+    rest.split_at_checked(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn long(bytes: &[u8]) -> Vec<u8> {
+        [&(bytes.len() as u32).to_be_bytes()[..], bytes].concat()
+    }
+
+    fn entry(name: &str, kind: u8, value: &[u8]) -> Vec<u8> {
+        [&[name.len() as u8][..], name.as_bytes(), &[kind], value].concat()
+    }
+
+    fn connection_start_frame() -> Vec<u8> {
+        let capabilities = long(&entry("publisher_confirms", b't', &[1]));
+        let props = [
+            entry("capabilities", b'F', &capabilities),
+            entry("cluster_name", b'S', &long(b"rabbit@mq1")),
+            entry("product", b'S', &long(b"RabbitMQ")),
+            entry("version", b'S', &long(b"3.12.0")),
+            entry("platform", b'S', &long(b"Erlang/OTP 25.3")),
+        ]
+        .concat();
+        let payload = [
+            &[0, 10, 0, 10, 0, 9][..],
+            &long(&props),
+            &long(b"PLAIN AMQPLAIN"),
+            &long(b"en_US"),
+        ]
+        .concat();
+        [
+            &[FRAME_METHOD, 0, 0][..],
+            &(payload.len() as u32).to_be_bytes(),
+            &payload,
+            &[FRAME_END],
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn reads_product_and_version_from_connection_start() {
+        let frame = connection_start_frame();
+        assert_eq!(
+            parse_reply(&frame),
+            Some(Some("amqp rabbitmq 3.12.0".to_string()))
+        );
+        // A partial frame asks for more bytes.
+        assert_eq!(parse_reply(&frame[..20]), None);
+        assert_eq!(parse_reply(b"HTTP/1.1 400"), Some(None));
+    }
+
+    #[test]
+    fn unsupported_version_reply_reports_broker_protocol() {
+        assert_eq!(
+            parse_reply(b"AMQP\x00\x01\x00\x00"),
+            Some(Some("amqp protocol 1-0-0".to_string()))
+        );
+    }
+}
@@ -0,0 +1,574 @@
+//! Command-line definition and config-file merging for the `lan-scan-rs` binary.
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser};
+use lan_scan_rs::alert::AlertFormat;
+use lan_scan_rs::config::{self, FileConfig};
+use lan_scan_rs::ports::{PortOrder, PortsFallback, ScanProfile};
+use lan_scan_rs::proxy::{self, HttpProxy};
+use lan_scan_rs::risk::Severity;
+use lan_scan_rs::types::TimestampFormat;
+use lan_scan_rs::{netdetect, resolve, scanner, server, snmp, source_port, webhook};
+
+/// `--ports` default; unlike a file named explicitly, it may be missing.
+pub const DEFAULT_PORTS_FILE: &str = "ports.txt";
+
+/// lan-scan-rs — Fast, safe-by-default async LAN TCP port scanner with a tiny embedded web UI.
+#[derive(Debug, Clone, Parser)]
+#[command(
+    name = "lan-scan-rs",
+    version,
+    about = "Fast, safe-by-default async LAN TCP port scanner with a tiny embedded web UI.",
+    long_about = None
+)]
+pub struct Cli {
+    /// CIDR (e.g., 192.168.1.0/24), IP, hostname, or path to file with those; "-" reads stdin. If omitted, auto-detect local /24.
+    #[arg(long, env = "LAN_SCAN_TARGETS")]
+    pub targets: Option<String>,
+
+    /// Read targets line by line from stdin (same format as a targets file) and scan as they arrive.
+    #[arg(long, default_value_t = false)]
+    pub stdin: bool,
+
+    /// File of `ip:port` pairs to scan as-is instead of every target x every port.
+    #[arg(long, conflicts_with_all = ["targets", "stdin"])]
+    pub pairs: Option<PathBuf>,
+
+    /// Path to ports list file (one port or range per line).
+    #[arg(long, env = "LAN_SCAN_PORTS_FILE", default_value = DEFAULT_PORTS_FILE)]
+    pub ports: PathBuf,
+
+    /// What to scan when the --ports file is missing (only the default ports.txt may be) or
+    /// lists no ports: default (built-in set), quick (the --quick set), or none (an error).
+    #[arg(long = "ports-fallback", default_value = "default")]
+    pub ports_fallback: PortsFallback,
+
+    /// Scan this port (repeatable). Combined with --ports-inline; inline ports win over --ports/--quick.
+    #[arg(long = "port", value_parser = clap::value_parser!(u16).range(1..))]
+    pub port: Vec<u16>,
+
+    /// Inline ports list, e.g. 22,80,443 or 8000-8010. Wins over --ports/--quick. From the
+    /// environment it only applies when no ports were chosen on the command line.
+    #[arg(long = "ports-inline", env = "LAN_SCAN_PORTS")]
+    pub ports_inline: Option<String>,
+
+    /// Max concurrent TCP connect attempts.
+    #[arg(long, env = "LAN_SCAN_CONCURRENCY", default_value_t = 1000)]
+    pub concurrency: usize,
+
+    /// Socket connect timeout in milliseconds.
+    #[arg(
+        long = "timeout-ms",
+        env = "LAN_SCAN_TIMEOUT_MS",
+        default_value_t = 400
+    )]
+    pub timeout_ms: u64,
+
+    /// Write results as pretty JSON to this path (optional).
+    #[arg(long, env = "LAN_SCAN_OUTPUT")]
+    pub output: Option<PathBuf>,
+
+    /// How timestamps are written (entries, generated_at, meta start/end, a saved baseline's
+    /// created_at): rfc3339 (UTC) or epoch-millis (milliseconds since 1970, still a JSON string).
+    #[arg(long = "timestamp-format", default_value = "rfc3339")]
+    pub timestamp_format: TimestampFormat,
+
+    /// Write RFC 3339 timestamps at this machine's UTC offset (e.g. +02:00) instead of in UTC;
+    /// stays UTC, with a warning, if the offset can't be determined.
+    #[arg(long = "local-time", default_value_t = false)]
+    pub local_time: bool,
+
+    /// Start the embedded HTTP UI server (serves static UI; endpoints TBD).
+    #[arg(long = "serve-ui", default_value_t = false)]
+    pub serve_ui: bool,
+
+    /// Bind address for the HTTP UI server (only used with --serve-ui).
+    #[arg(long, env = "LAN_SCAN_BIND", default_value = "127.0.0.1:8080")]
+    pub bind: String,
+
+    /// Directory of static UI files served with --serve-ui; unknown paths get its index.html.
+    #[arg(long = "ui-dir", default_value = server::DEFAULT_UI_DIR)]
+    pub ui_dir: PathBuf,
+
+    /// Enable Redis PING probe on port 6379 (optional, off by default).
+    #[arg(long = "probe-redis", default_value_t = false)]
+    pub probe_redis: bool,
+
+    /// Send an SMB2 NEGOTIATE on port 445 to report the dialect and whether signing is required.
+    #[arg(long = "probe-smb", default_value_t = false)]
+    pub probe_smb: bool,
+
+    /// Ask DNS servers on port 53 to resolve an external name and flag open recursive resolvers.
+    #[arg(long = "probe-dns-recursion", default_value_t = false)]
+    pub probe_dns_recursion: bool,
+
+    /// Send a Kafka ApiVersions request on port 9092 to confirm a broker and its API range.
+    #[arg(long = "probe-kafka", default_value_t = false)]
+    pub probe_kafka: bool,
+
+    /// Send a bare CRLF to open ports that stayed silent through every probe and read again.
+    #[arg(long = "grab-all", default_value_t = false)]
+    pub grab_all: bool,
+
+    /// Also record ports that refused the connection (host up, port closed) as `open: false`
+    /// entries; timed-out ports are still only counted.
+    #[arg(long = "note-refused", default_value_t = false)]
+    pub note_refused: bool,
+
+    /// Skip banner, TLS, and protocol probes: record open ports with latency and a port-number
+    /// service guess only (fast connect sweep).
+    #[arg(
+        long = "no-banner",
+        default_value_t = false,
+        conflicts_with = "grab_all"
+    )]
+    pub no_banner: bool,
+
+    /// Close each connection the moment it opens, never reading or writing, for fragile
+    /// industrial/IoT devices; open ports are recorded with latency only.
+    #[arg(
+        long = "connect-only",
+        default_value_t = false,
+        conflicts_with = "grab_all"
+    )]
+    pub connect_only: bool,
+
+    /// Also validate TLS certificates against the system trust store and record the result as
+    /// `tls_valid`; untrusted certificates are still summarized.
+    #[arg(long = "tls-verify", default_value_t = false)]
+    pub tls_verify: bool,
+
+    /// Time allowed for each TLS handshake, in milliseconds; --probe-budget-ms still caps the
+    /// whole TLS probe.
+    #[arg(
+        long = "tls-timeout-ms",
+        default_value_t = scanner::DEFAULT_TLS_TIMEOUT.as_millis() as u64
+    )]
+    pub tls_timeout_ms: u64,
+
+    /// PEM client certificate presented to mutual-TLS services (needs --tls-client-key).
+    #[arg(long = "tls-client-cert", requires = "tls_client_key")]
+    pub tls_client_cert: Option<PathBuf>,
+
+    /// PEM PKCS#8 private key for --tls-client-cert.
+    #[arg(long = "tls-client-key", requires = "tls_client_cert")]
+    pub tls_client_key: Option<PathBuf>,
+
+    /// Comma-separated ALPN protocols the TLS probe offers, most preferred first; the server's
+    /// pick is recorded as `alpn`. Pass an empty value to send no ALPN extension.
+    #[arg(
+        long = "tls-alpn",
+        value_delimiter = ',',
+        default_value = "h2,http/1.1"
+    )]
+    pub tls_alpn: Vec<String>,
+
+    /// Most bytes kept from a service's unprompted banner; multi-packet greetings are read until
+    /// the server goes quiet.
+    #[arg(long = "banner-bytes", default_value_t = scanner::DEFAULT_BANNER_BYTES)]
+    pub banner_bytes: usize,
+
+    /// Cap the total time spent probing each open port (banner, TLS, protocol probes); whatever
+    /// was learned when it runs out is kept.
+    #[arg(long = "probe-budget-ms")]
+    pub probe_budget_ms: Option<u64>,
+
+    /// After the TCP scan, try these SNMP v2c communities against UDP 161 on every host
+    /// (comma-separated; bare flag = public). Off unless given.
+    #[arg(
+        long = "snmp-communities",
+        value_delimiter = ',',
+        num_args = 0..=1,
+        default_missing_value = snmp::DEFAULT_COMMUNITY
+    )]
+    pub snmp_communities: Vec<String>,
+
+    /// Re-scan hosts that had no open ports up to N more times, with a longer timeout each pass.
+    #[arg(long = "retry-empty-hosts", default_value_t = 0)]
+    pub retry_empty_hosts: u32,
+
+    /// Quick scan preset (smaller port set, faster timeouts when unspecified).
+    #[arg(long, default_value_t = false)]
+    pub quick: bool,
+
+    /// Preset port set plus matching probes: quick (the --quick ports), full (built-in set with
+    /// Redis/SMB/Kafka probes), web (HTTP/TLS ports), db (database ports with the Redis probe).
+    /// Inline ports still win; probe flags add to the profile's.
+    #[arg(long, conflicts_with = "quick")]
+    pub profile: Option<ScanProfile>,
+
+    /// Exclude specific ports (comma-separated list or ranges: e.g., 53,135-139).
+    #[arg(long = "exclude-ports")]
+    pub exclude_ports: Option<String>,
+
+    /// User-Agent header sent by the HTTP probe (pass "" to omit the header).
+    #[arg(long = "http-user-agent", default_value = scanner::DEFAULT_HTTP_USER_AGENT)]
+    pub http_user_agent: String,
+
+    /// Force this Host header for HTTP probes (defaults to the target's hostname, else its IP).
+    #[arg(long = "http-host")]
+    pub http_host: Option<String>,
+
+    /// Comma-separated paths for the HTTP probe to try in order (e.g., /,/login,/admin).
+    #[arg(long = "http-paths", value_delimiter = ',', default_value = "/")]
+    pub http_paths: Vec<String>,
+
+    /// Max concurrent connects to any single host, protecting fragile devices (default: unlimited).
+    #[arg(long = "per-host-concurrency", env = "LAN_SCAN_PER_HOST_CONCURRENCY")]
+    pub per_host_concurrency: Option<usize>,
+
+    /// Scan each host's ports one at a time (hosts still in parallel) and group the results by
+    /// host, for reports that come out the same way on every run. Overrides --per-host-concurrency.
+    #[arg(long = "host-sequential", default_value_t = false)]
+    pub host_sequential: bool,
+
+    /// Grow from one in-flight connect to --concurrency over this window (e.g. 5s), sparing
+    /// cheap switches the initial burst.
+    #[arg(long = "ramp-up", value_parser = config::parse_duration)]
+    pub ramp_up: Option<Duration>,
+
+    /// Bind every connect to a local port in this range (e.g. 40000-50000), cycling through it;
+    /// when all are in use, connects wait for one to free up.
+    #[arg(long = "source-port-range", value_parser = source_port::parse_port_range)]
+    pub source_port_range: Option<RangeInclusive<u16>>,
+
+    /// Reach targets through this HTTP proxy's CONNECT tunnel (e.g. http://10.0.0.1:3128); the
+    /// probes run over the tunnel. SNMP (UDP) and --auto-timeout don't use it.
+    #[arg(long, value_parser = proxy::HttpProxy::parse)]
+    pub proxy: Option<HttpProxy>,
+
+    /// Basic credentials (user:pass) sent to the --proxy with every CONNECT.
+    #[arg(long = "proxy-auth", requires = "proxy")]
+    pub proxy_auth: Option<String>,
+
+    /// Work order: as-given (host by host), common-first (the most commonly open ports on
+    /// every host before rarer ones; with --per-host-concurrency, only each host's ports are reordered),
+    /// or random (host by host, ports shuffled with --seed).
+    #[arg(long = "order", default_value = "as-given")]
+    pub order: PortOrder,
+
+    /// Seed for the scan's RNG, which shuffles the ports under --order random; without it a seed
+    /// is drawn from entropy and printed, so the run's order can be replayed.
+    #[arg(long = "seed")]
+    pub seed: Option<u64>,
+
+    /// Expand targets and ports, print the work plan, and exit without opening any sockets.
+    #[arg(long = "dry-run", default_value_t = false)]
+    pub dry_run: bool,
+
+    /// Validate the --ports file and report its ports, collapsed duplicates, and every bad line,
+    /// then exit without scanning.
+    #[arg(long = "check-ports", default_value_t = false)]
+    pub check_ports: bool,
+
+    /// Load defaults from a TOML profile; flags given on the command line override it.
+    #[arg(long, env = "LAN_SCAN_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Send each open-port finding to a syslog collector over UDP (RFC 5424), e.g. siem:514.
+    #[arg(long)]
+    pub syslog: Option<String>,
+
+    /// Send each open-port finding to the local syslog socket (/dev/log).
+    #[arg(long = "syslog-local", default_value_t = false)]
+    pub syslog_local: bool,
+
+    /// Calibrate the connect timeout from measured RTTs (5x p95); falls back to --timeout-ms.
+    #[arg(long = "auto-timeout", default_value_t = false)]
+    pub auto_timeout: bool,
+
+    /// POST a JSON summary to this http(s) URL when a scan completes (CLI and UI scans).
+    #[arg(long, env = "LAN_SCAN_WEBHOOK", hide_env_values = true)]
+    pub webhook: Option<String>,
+
+    /// Time allowed for the webhook request, in milliseconds.
+    #[arg(
+        long = "webhook-timeout-ms",
+        default_value_t = webhook::DEFAULT_WEBHOOK_TIMEOUT.as_millis() as u64
+    )]
+    pub webhook_timeout_ms: u64,
+
+    /// Webhook body: json (summary), slack, or discord. Chat formats only post when there are findings.
+    #[arg(long = "alert-format", default_value = "json")]
+    pub alert_format: AlertFormat,
+
+    /// Baseline to compare against (from --save-baseline, or any previous results JSON): open
+    /// ports are marked new or unchanged, baseline ports no longer open are listed as missing,
+    /// and the webhook reports the new ones.
+    #[arg(long)]
+    pub baseline: Option<PathBuf>,
+
+    /// Save this scan's open ports as a baseline file for later --baseline / --fail-on-new runs.
+    #[arg(long = "save-baseline")]
+    pub save_baseline: Option<PathBuf>,
+
+    /// Re-scan on this interval until Ctrl+C (e.g. 90s, 30m, 1h30m), writing timestamped results.
+    #[arg(long, value_parser = config::parse_duration)]
+    pub every: Option<Duration>,
+
+    /// Stop a scan that runs longer than this (e.g. 10m) and keep its partial results, flagged
+    /// `timed_out`; with --every, each run gets the full limit.
+    #[arg(long = "max-duration", value_parser = config::parse_duration)]
+    pub max_duration: Option<Duration>,
+
+    /// Only list findings at or above this severity in the table: info, low, medium, high, critical.
+    #[arg(long = "min-severity")]
+    pub min_severity: Option<Severity>,
+
+    /// Print one table row per host listing its open ports, e.g. `10.0.0.5  22(ssh), 80(http)`.
+    #[arg(long = "group-by-host", default_value_t = false)]
+    pub group_by_host: bool,
+
+    /// Print the open ports as an nmap `-p` list (e.g. 22,80,443,8000-8002) to stderr, or write
+    /// it to this file; keeps JSON on stdout clean.
+    #[arg(long = "ports-summary", num_args = 0..=1, default_missing_value = "-")]
+    pub ports_summary: Option<PathBuf>,
+
+    /// With --ports-summary, add one `<ip> <ports>` line per host after the union.
+    #[arg(
+        long = "ports-summary-per-host",
+        default_value_t = false,
+        requires = "ports_summary"
+    )]
+    pub ports_summary_per_host: bool,
+
+    /// Never color terminal output (color is also off when piped or when NO_COLOR is set).
+    #[arg(long = "no-color", default_value_t = false)]
+    pub no_color: bool,
+
+    /// Print only results and errors; with --output, stdout stays empty (`--output -` prints only
+    /// the JSON).
+    #[arg(short, long, default_value_t = false, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Also print each finding as it is found and which probes are active.
+    #[arg(short, long, default_value_t = false)]
+    pub verbose: bool,
+
+    /// Exit with status 1 if any open port is found (CI gating).
+    #[arg(long = "fail-on-open", default_value_t = false)]
+    pub fail_on_open: bool,
+
+    /// Exit with status 1 only if ports are open that are not in this baseline (a --save-baseline
+    /// file or a previous results JSON).
+    #[arg(long = "fail-on-new", value_name = "BASELINE")]
+    pub fail_on_new: Option<PathBuf>,
+
+    /// Refuse to expand CIDRs with a shorter prefix than this (default /16) unless --force.
+    #[arg(
+        long = "min-prefix-len",
+        default_value_t = netdetect::DEFAULT_MIN_PREFIX_LEN,
+        value_parser = clap::value_parser!(u8).range(0..=32)
+    )]
+    pub min_prefix_len: u8,
+
+    /// Expand target networks of any size (disables the --min-prefix-len guard).
+    #[arg(long, default_value_t = false)]
+    pub force: bool,
+
+    /// Hostname targets resolved at once; each lookup has its own --dns-timeout-ms.
+    #[arg(long = "dns-concurrency", default_value_t = resolve::DEFAULT_CONCURRENCY)]
+    pub dns_concurrency: usize,
+
+    /// Give up on a hostname target that hasn't resolved after this long (ms).
+    #[arg(
+        long = "dns-timeout-ms",
+        default_value_t = resolve::DEFAULT_TIMEOUT.as_millis() as u64
+    )]
+    pub dns_timeout_ms: u64,
+
+    /// Browse mDNS/Bonjour (~3s) first; advertised ports on target hosts join the scan, annotated with the service.
+    #[arg(long, default_value_t = false)]
+    pub mdns: bool,
+
+    /// Send an SSDP M-SEARCH (~3s) first; UPnP devices among the targets are annotated with type/manufacturer/model.
+    #[arg(long, default_value_t = false)]
+    pub ssdp: bool,
+}
+
+/// Parse the command line and `LAN_SCAN_*` environment variables, and layer a `--config` file
+/// underneath them.
+///
+/// Precedence is defaults < config file < environment < command line: a file value only
+/// replaces a field that neither the command line nor the environment set.
+pub fn parse() -> Result<Cli> {
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches)?;
+    if let Some(path) = cli.config.clone() {
+        let file = config::load_config(&path)?;
+        apply_file_config(&mut cli, &file, &matches);
+    }
+    drop_env_ports_under_cli_ports(&mut cli, &matches);
+    Ok(cli)
+}
+
+fn set_by_user(matches: &ArgMatches, id: &str) -> bool {
+    matches!(
+        matches.value_source(id),
+        Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+    )
+}
+
+/// `LAN_SCAN_PORTS` fills in for --ports-inline, which beats every other port choice; a
+/// deployment-wide default must not beat ports picked on the command line.
+fn drop_env_ports_under_cli_ports(cli: &mut Cli, matches: &ArgMatches) {
+    let from_env = matches.value_source("ports_inline") == Some(ValueSource::EnvVariable);
+    let cli_ports = ["ports", "port", "quick", "profile"]
+        .iter()
+        .any(|id| matches.value_source(id) == Some(ValueSource::CommandLine));
+    if from_env && cli_ports {
+        cli.ports_inline = None;
+    }
+}
+
+/// Copy values from `file` into `cli` for every field the command line and environment left unset.
+fn apply_file_config(cli: &mut Cli, file: &FileConfig, matches: &ArgMatches) {
+    // Plain fields take the file value as-is; optional fields wrap it in `Some`.
+    macro_rules! merge {
+        ($field:ident) => {
+            if !set_by_user(matches, stringify!($field)) {
+                if let Some(v) = &file.$field {
+                    cli.$field = v.clone();
+                }
+            }
+        };
+        ($field:ident, opt) => {
+            if !set_by_user(matches, stringify!($field)) {
+                if let Some(v) = &file.$field {
+                    cli.$field = Some(v.clone());
+                }
+            }
+        };
+    }
+    merge!(targets, opt);
+    merge!(pairs, opt);
+    merge!(ports);
+    merge!(ports_fallback);
+    merge!(port);
+    merge!(ports_inline, opt);
+    merge!(concurrency);
+    merge!(timeout_ms);
+    merge!(output, opt);
+    merge!(timestamp_format);
+    merge!(local_time);
+    merge!(bind);
+    merge!(ui_dir);
+    merge!(probe_redis);
+    merge!(probe_smb);
+    merge!(probe_dns_recursion);
+    merge!(probe_kafka);
+    merge!(grab_all);
+    merge!(note_refused);
+    merge!(no_banner);
+    merge!(connect_only);
+    merge!(tls_verify);
+    merge!(tls_timeout_ms);
+    merge!(tls_client_cert, opt);
+    merge!(tls_client_key, opt);
+    merge!(tls_alpn);
+    merge!(banner_bytes);
+    merge!(probe_budget_ms, opt);
+    merge!(snmp_communities);
+    merge!(retry_empty_hosts);
+    merge!(quick);
+    merge!(profile, opt);
+    merge!(exclude_ports, opt);
+    merge!(http_user_agent);
+    merge!(http_host, opt);
+    merge!(http_paths);
+    merge!(per_host_concurrency, opt);
+    merge!(host_sequential);
+    merge!(ramp_up, opt);
+    merge!(source_port_range, opt);
+    merge!(proxy, opt);
+    merge!(proxy_auth, opt);
+    merge!(order);
+    merge!(seed, opt);
+    merge!(auto_timeout);
+    merge!(syslog, opt);
+    merge!(syslog_local);
+    merge!(webhook, opt);
+    merge!(webhook_timeout_ms);
+    merge!(alert_format);
+    merge!(baseline, opt);
+    merge!(save_baseline, opt);
+    merge!(every, opt);
+    merge!(max_duration, opt);
+    merge!(min_severity, opt);
+    merge!(group_by_host);
+    merge!(ports_summary, opt);
+    merge!(ports_summary_per_host);
+    merge!(no_color);
+    merge!(quiet);
+    merge!(verbose);
+    merge!(fail_on_open);
+    merge!(fail_on_new, opt);
+    merge!(min_prefix_len);
+    merge!(dns_concurrency);
+    merge!(dns_timeout_ms);
+    merge!(force);
+    merge!(mdns);
+    merge!(ssdp);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_with(args: &[&str], toml: &str) -> Cli {
+        let matches = Cli::command()
+            .try_get_matches_from(std::iter::once("lan-scan-rs").chain(args.iter().copied()))
+            .unwrap();
+        let mut cli = Cli::from_arg_matches(&matches).unwrap();
+        let file = config::parse_config(toml).unwrap();
+        apply_file_config(&mut cli, &file, &matches);
+        cli
+    }
+
+    #[test]
+    fn file_overrides_defaults() {
+        let cli = parse_with(
+            &[],
+            "concurrency = 50\ntargets = \"10.0.0.0/30\"\nquick = true\n",
+        );
+        assert_eq!(cli.concurrency, 50);
+        assert_eq!(cli.targets.as_deref(), Some("10.0.0.0/30"));
+        assert!(cli.quick);
+        assert_eq!(cli.timeout_ms, 400, "untouched fields keep their defaults");
+    }
+
+    #[test]
+    fn cli_overrides_file() {
+        let cli = parse_with(
+            &["--concurrency", "7", "--targets", "10.1.1.1"],
+            "concurrency = 50\ntargets = \"10.0.0.0/30\"\ntimeout-ms = 900\n",
+        );
+        assert_eq!(cli.concurrency, 7);
+        assert_eq!(cli.targets.as_deref(), Some("10.1.1.1"));
+        assert_eq!(cli.timeout_ms, 900);
+    }
+
+    #[test]
+    fn env_ports_sit_between_file_and_command_line() {
+        // Only this test sets LAN_SCAN_PORTS, and no other test looks at ports_inline.
+        std::env::set_var("LAN_SCAN_PORTS", "22,80");
+        let parse_env = |args: &[&str], toml: &str| {
+            let matches = Cli::command()
+                .try_get_matches_from(std::iter::once("lan-scan-rs").chain(args.iter().copied()))
+                .unwrap();
+            let mut cli = parse_with(args, toml);
+            drop_env_ports_under_cli_ports(&mut cli, &matches);
+            cli
+        };
+        let cli = parse_env(&[], "ports-inline = \"443\"\n");
+        assert_eq!(cli.ports_inline.as_deref(), Some("22,80"));
+        let cli = parse_env(&["--port", "8080"], "");
+        assert_eq!(cli.ports_inline, None);
+        assert_eq!(cli.port, [8080]);
+        std::env::remove_var("LAN_SCAN_PORTS");
+    }
+}
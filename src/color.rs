@@ -0,0 +1,53 @@
+//! ANSI colors for terminal output. Only used when stdout is a terminal and neither `--no-color`
+//! nor `NO_COLOR` (https://no-color.org) asks otherwise, so piped output stays plain.
+use std::io::IsTerminal;
+
+use lan_scan_rs::risk::Severity;
+use lan_scan_rs::types::ScanEntry;
+
+const GREEN: &str = "32";
+const YELLOW: &str = "33";
+const RED: &str = "31";
+const BOLD_RED: &str = "1;31";
+
+/// Whether to emit color, decided once per run.
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    enabled: bool,
+}
+
+impl Palette {
+    pub fn detect(no_color: bool) -> Self {
+        let env_off = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        Self {
+            enabled: !no_color && !env_off && std::io::stdout().is_terminal(),
+        }
+    }
+
+    /// Wrap `text` in the SGR `code`, or return it untouched when color is off.
+    pub fn paint(self, code: &str, text: &str) -> String {
+        if self.enabled {
+            format!("\x1b[{code}m{text}\x1b[0m")
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// A results-table row: red for high/critical findings, yellow for anything a probe identified
+    /// or rated above info, green for a plain open port.
+    pub fn entry_row(self, e: &ScanEntry, row: &str) -> String {
+        let code = match e.severity.unwrap_or(Severity::Info) {
+            Severity::Critical => BOLD_RED,
+            Severity::High => RED,
+            Severity::Medium | Severity::Low => YELLOW,
+            Severity::Info if e.banner.is_some() => YELLOW,
+            Severity::Info => GREEN,
+        };
+        self.paint(code, row)
+    }
+
+    /// Call-outs for serious findings printed after the table.
+    pub fn alert(self, text: &str) -> String {
+        self.paint(RED, text)
+    }
+}
@@ -0,0 +1,229 @@
+use crate::alert::AlertFormat;
+use crate::ports::{PortOrder, PortsFallback, ScanProfile};
+use crate::proxy::HttpProxy;
+use crate::risk::Severity;
+use crate::source_port;
+use crate::types::TimestampFormat;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Deserializer};
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A scan profile loaded from a TOML file via `--config`.
+///
+/// Keys mirror the long CLI flags (`timeout-ms`, `probe-redis`, ...). Every field is optional;
+/// anything left out keeps the CLI default, and flags given on the command line win over the file.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FileConfig {
+    pub targets: Option<String>,
+    pub pairs: Option<PathBuf>,
+    pub ports: Option<PathBuf>,
+    pub ports_fallback: Option<PortsFallback>,
+    pub port: Option<Vec<u16>>,
+    pub ports_inline: Option<String>,
+    pub concurrency: Option<usize>,
+    pub timeout_ms: Option<u64>,
+    pub output: Option<PathBuf>,
+    pub timestamp_format: Option<TimestampFormat>,
+    pub local_time: Option<bool>,
+    pub bind: Option<String>,
+    pub ui_dir: Option<PathBuf>,
+    pub probe_redis: Option<bool>,
+    pub probe_smb: Option<bool>,
+    pub probe_dns_recursion: Option<bool>,
+    pub probe_kafka: Option<bool>,
+    pub grab_all: Option<bool>,
+    pub note_refused: Option<bool>,
+    pub no_banner: Option<bool>,
+    pub connect_only: Option<bool>,
+    pub tls_verify: Option<bool>,
+    pub tls_timeout_ms: Option<u64>,
+    pub tls_client_cert: Option<PathBuf>,
+    pub tls_client_key: Option<PathBuf>,
+    pub tls_alpn: Option<Vec<String>>,
+    pub banner_bytes: Option<usize>,
+    pub probe_budget_ms: Option<u64>,
+    pub snmp_communities: Option<Vec<String>>,
+    pub retry_empty_hosts: Option<u32>,
+    pub quick: Option<bool>,
+    pub profile: Option<ScanProfile>,
+    pub exclude_ports: Option<String>,
+    pub http_user_agent: Option<String>,
+    pub http_host: Option<String>,
+    pub http_paths: Option<Vec<String>>,
+    pub per_host_concurrency: Option<usize>,
+    pub host_sequential: Option<bool>,
+    #[serde(default, deserialize_with = "de_duration")]
+    pub ramp_up: Option<Duration>,
+    #[serde(default, deserialize_with = "de_port_range")]
+    pub source_port_range: Option<RangeInclusive<u16>>,
+    #[serde(default, deserialize_with = "de_proxy")]
+    pub proxy: Option<HttpProxy>,
+    pub proxy_auth: Option<String>,
+    pub order: Option<PortOrder>,
+    pub seed: Option<u64>,
+    pub auto_timeout: Option<bool>,
+    pub syslog: Option<String>,
+    pub syslog_local: Option<bool>,
+    pub webhook: Option<String>,
+    pub webhook_timeout_ms: Option<u64>,
+    pub alert_format: Option<AlertFormat>,
+    pub baseline: Option<PathBuf>,
+    pub save_baseline: Option<PathBuf>,
+    #[serde(default, deserialize_with = "de_duration")]
+    pub every: Option<Duration>,
+    #[serde(default, deserialize_with = "de_duration")]
+    pub max_duration: Option<Duration>,
+    pub min_severity: Option<Severity>,
+    pub group_by_host: Option<bool>,
+    pub ports_summary: Option<PathBuf>,
+    pub ports_summary_per_host: Option<bool>,
+    pub no_color: Option<bool>,
+    pub quiet: Option<bool>,
+    pub verbose: Option<bool>,
+    pub fail_on_open: Option<bool>,
+    pub fail_on_new: Option<PathBuf>,
+    pub min_prefix_len: Option<u8>,
+    pub dns_concurrency: Option<usize>,
+    pub dns_timeout_ms: Option<u64>,
+    pub force: Option<bool>,
+    pub mdns: Option<bool>,
+    pub ssdp: Option<bool>,
+}
+
+/// Parse a TOML profile. Unknown keys are rejected so typos don't silently do nothing.
+pub fn parse_config(s: &str) -> Result<FileConfig> {
+    let cfg: FileConfig = toml::from_str(s)?;
+    cfg.validate()?;
+    Ok(cfg)
+}
+
+impl FileConfig {
+    /// Apply the same range checks clap enforces on the matching flags, since file values are
+    /// merged in after argument parsing.
+    fn validate(&self) -> Result<()> {
+        if self.port.iter().flatten().any(|&p| p == 0) {
+            bail!("invalid port: 0 is not in 1..=65535");
+        }
+        if let Some(len) = self.min_prefix_len.filter(|&len| len > 32) {
+            bail!("invalid min-prefix-len: {len} is not in 0..=32");
+        }
+        Ok(())
+    }
+}
+
+/// Load and parse a TOML profile from disk.
+pub fn load_config(path: impl AsRef<Path>) -> Result<FileConfig> {
+    let path = path.as_ref();
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("failed to read config file: {}", path.display()))?;
+    parse_config(&content).with_context(|| format!("invalid config file: {}", path.display()))
+}
+
+/// Parse an interval like `45s`, `30m`, `2h`, `1d`, or `1h30m`; a bare number means seconds.
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    if let Ok(secs) = s.parse::<u64>() {
+        return check_nonzero(Duration::from_secs(secs), s);
+    }
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86_400,
+            _ => bail!("invalid duration {s:?}: unknown unit '{c}' (use s, m, h, d)"),
+        };
+        let n: u64 = digits
+            .parse()
+            .map_err(|_| anyhow!("invalid duration {s:?}: expected a number before '{c}'"))?;
+        total = total.saturating_add(n.saturating_mul(unit));
+        digits.clear();
+    }
+    if !digits.is_empty() {
+        bail!("invalid duration {s:?}: missing unit after {digits}");
+    }
+    check_nonzero(Duration::from_secs(total), s)
+}
+
+fn check_nonzero(d: Duration, s: &str) -> Result<Duration> {
+    if d.is_zero() {
+        bail!("invalid duration {s:?}: must be greater than zero");
+    }
+    Ok(d)
+}
+
+fn de_duration<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Duration>, D::Error> {
+    let s = String::deserialize(d)?;
+    parse_duration(&s)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+fn de_port_range<'de, D: Deserializer<'de>>(d: D) -> Result<Option<RangeInclusive<u16>>, D::Error> {
+    let s = String::deserialize(d)?;
+    source_port::parse_port_range(&s)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+fn de_proxy<'de, D: Deserializer<'de>>(d: D) -> Result<Option<HttpProxy>, D::Error> {
+    let s = String::deserialize(d)?;
+    HttpProxy::parse(&s)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_kebab_case_keys() {
+        let cfg = parse_config(
+            r#"
+            targets = "192.168.1.0/24"
+            timeout-ms = 300
+            probe-redis = true
+            http-paths = ["/", "/login"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(cfg.targets.as_deref(), Some("192.168.1.0/24"));
+        assert_eq!(cfg.timeout_ms, Some(300));
+        assert_eq!(cfg.probe_redis, Some(true));
+        assert_eq!(cfg.http_paths.unwrap(), vec!["/", "/login"]);
+        assert_eq!(cfg.concurrency, None);
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(parse_config("timeout = 300\n").is_err());
+    }
+
+    #[test]
+    fn out_of_range_values_are_rejected() {
+        assert!(parse_config("port = [22, 0]\n").is_err());
+        assert!(parse_config("min-prefix-len = 33\n").is_err());
+        assert!(parse_config("port = [22]\nmin-prefix-len = 32\n").is_ok());
+    }
+
+    #[test]
+    fn parses_intervals() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("10x").is_err());
+        assert!(parse_duration("1h30").is_err());
+    }
+}
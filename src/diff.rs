@@ -0,0 +1,246 @@
+//! Comparing results against a baseline: the open ports of an earlier scan, saved with
+//! `--save-baseline` (or any results JSON) and checked with `--baseline` / `--fail-on-new`.
+use crate::schema;
+use crate::types::{BaselinePort, BaselineStatus, Protocol, ScanEntry, ScanResults, Timestamps};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// `kind` of a baseline file, telling it apart from a results document.
+pub const BASELINE_KIND: &str = "lan-scan-baseline";
+/// Version of the baseline file shape.
+pub const BASELINE_VERSION: u32 = 1;
+
+/// The open set of one scan, kept as a durable artifact to compare later scans against.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Baseline {
+    /// Always `BASELINE_KIND`.
+    pub kind: String,
+    pub version: u32,
+    /// RFC 3339 UTC time the baseline was saved.
+    pub created_at: String,
+    pub tool_version: String,
+    /// When the scan it was taken from finished (its `generated_at`).
+    pub scanned_at: String,
+    /// Targets of that scan as the user gave them, when known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_spec: Option<String>,
+    /// Open ports, one per `ip:port` and protocol.
+    pub ports: Vec<BaselinePort>,
+}
+
+impl Baseline {
+    /// The open ports of `results`; vhost entries sharing an `ip:port` collapse into one.
+    /// `created_at` is written in `stamps`.
+    pub fn from_results(results: &ScanResults, stamps: Timestamps) -> Self {
+        let mut seen = HashSet::new();
+        let ports = results
+            .entries
+            .iter()
+            .filter(|e| e.open && seen.insert(key(e)))
+            .map(|e| BaselinePort {
+                ip: e.ip.clone(),
+                port: e.port,
+                protocol: e.protocol,
+                service: e.service.clone(),
+            })
+            .collect();
+        Self {
+            kind: BASELINE_KIND.to_string(),
+            version: BASELINE_VERSION,
+            created_at: stamps.now(),
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            scanned_at: results.generated_at.clone(),
+            target_spec: results.meta.as_ref().map(|m| m.target_spec.clone()),
+            ports,
+        }
+    }
+
+    fn keys(&self) -> HashSet<(&str, u16, Protocol)> {
+        self.ports
+            .iter()
+            .map(|p| (p.ip.as_str(), p.port, p.protocol))
+            .collect()
+    }
+}
+
+/// Load a baseline: a file written by `save_baseline`, or a previous scan's JSON output (any
+/// schema version), whose open ports are taken as the baseline.
+pub fn load_baseline(path: impl AsRef<Path>) -> Result<Baseline> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("failed to read baseline: {}", path.display()))?;
+    parse_baseline(&text).with_context(|| format!("invalid baseline file: {}", path.display()))
+}
+
+fn parse_baseline(text: &str) -> Result<Baseline> {
+    let doc: Value = serde_json::from_str(text)?;
+    if doc.get("kind").and_then(Value::as_str) == Some(BASELINE_KIND) {
+        let version = doc.get("version").and_then(Value::as_u64).unwrap_or(0);
+        anyhow::ensure!(
+            version <= u64::from(BASELINE_VERSION),
+            "baseline version {version} is newer than supported version {BASELINE_VERSION}"
+        );
+        return Ok(serde_json::from_value(doc)?);
+    }
+    Ok(Baseline::from_results(
+        &schema::parse_results(text)?,
+        Timestamps::default(),
+    ))
+}
+
+/// Write `baseline` as pretty JSON for later `--baseline` runs.
+pub fn save_baseline(path: impl AsRef<Path>, baseline: &Baseline) -> Result<()> {
+    let path = path.as_ref();
+    let json = serde_json::to_string_pretty(baseline)?;
+    fs::write(path, json).with_context(|| format!("failed to write baseline: {}", path.display()))
+}
+
+/// Open entries in `current` whose `ip:port` (per protocol, so UDP 161 and TCP 161 are distinct)
+/// was not open in `baseline`, in `current` order.
+pub fn new_open_entries(baseline: &Baseline, current: &ScanResults) -> Vec<ScanEntry> {
+    let known = baseline.keys();
+    current
+        .entries
+        .iter()
+        .filter(|e| e.open && !known.contains(&key(e)))
+        .cloned()
+        .collect()
+}
+
+/// Mark each open entry `new` or `unchanged` against `baseline`, and list the baseline ports
+/// that are no longer open in `baseline_missing`. Ports outside this scan's targets count as
+/// missing too, so compare scans of the same scope.
+pub fn annotate(baseline: &Baseline, results: &mut ScanResults) {
+    let known = baseline.keys();
+    let open: HashSet<_> = results.entries.iter().filter(|e| e.open).map(key).collect();
+    let missing = baseline
+        .ports
+        .iter()
+        .filter(|p| !open.contains(&(p.ip.as_str(), p.port, p.protocol)))
+        .cloned()
+        .collect();
+    for e in results.entries.iter_mut().filter(|e| e.open) {
+        e.baseline = Some(if known.contains(&key(e)) {
+            BaselineStatus::Unchanged
+        } else {
+            BaselineStatus::New
+        });
+    }
+    results.baseline_missing = missing;
+}
+
+fn key(e: &ScanEntry) -> (&str, u16, Protocol) {
+    (e.ip.as_str(), e.port, e.protocol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_unseen_ports_are_new() {
+        let baseline = ScanResults {
+            entries: vec![
+                ScanEntry::test_open("10.0.0.1", 22),
+                ScanEntry::test_open("10.0.0.1", 80),
+            ],
+            ..Default::default()
+        };
+        let current = ScanResults {
+            entries: vec![
+                ScanEntry::test_open("10.0.0.1", 22),
+                ScanEntry::test_open("10.0.0.1", 443),
+                ScanEntry::test_open("10.0.0.2", 80),
+            ],
+            ..Default::default()
+        };
+        let new: Vec<(String, u16)> = new_open_entries(
+            &Baseline::from_results(&baseline, Timestamps::default()),
+            &current,
+        )
+        .into_iter()
+        .map(|e| (e.ip, e.port))
+        .collect();
+        assert_eq!(new, vec![("10.0.0.1".into(), 443), ("10.0.0.2".into(), 80)]);
+    }
+
+    #[test]
+    fn udp_and_tcp_on_the_same_port_are_distinct() {
+        let baseline = ScanResults {
+            entries: vec![ScanEntry::test_open("10.0.0.1", 161)],
+            ..Default::default()
+        };
+        let snmp = ScanEntry {
+            protocol: Protocol::Udp,
+            ..ScanEntry::test_open("10.0.0.1", 161)
+        };
+        let current = ScanResults {
+            entries: vec![ScanEntry::test_open("10.0.0.1", 161), snmp.clone()],
+            ..Default::default()
+        };
+        assert_eq!(
+            new_open_entries(
+                &Baseline::from_results(&baseline, Timestamps::default()),
+                &current
+            ),
+            vec![snmp]
+        );
+    }
+
+    #[test]
+    fn annotates_new_unchanged_and_missing_ports() {
+        let baseline = Baseline::from_results(
+            &ScanResults {
+                entries: vec![
+                    ScanEntry::test_open("10.0.0.1", 22),
+                    ScanEntry::test_open("10.0.0.1", 80),
+                ],
+                ..Default::default()
+            },
+            Timestamps::default(),
+        );
+        let mut current = ScanResults {
+            entries: vec![
+                ScanEntry::test_open("10.0.0.1", 22),
+                ScanEntry::test_open("10.0.0.1", 443),
+            ],
+            ..Default::default()
+        };
+        annotate(&baseline, &mut current);
+        let status: Vec<_> = current.entries.iter().map(|e| e.baseline).collect();
+        assert_eq!(
+            status,
+            vec![Some(BaselineStatus::Unchanged), Some(BaselineStatus::New)]
+        );
+        let missing: Vec<u16> = current.baseline_missing.iter().map(|p| p.port).collect();
+        assert_eq!(missing, vec![80]);
+    }
+
+    #[test]
+    fn loads_baseline_files_and_plain_results() {
+        let results = ScanResults {
+            generated_at: "2026-01-01T00:00:00Z".into(),
+            entries: vec![
+                ScanEntry::test_open("10.0.0.1", 22),
+                // A second vhost on the same port is one baseline port.
+                ScanEntry {
+                    host: Some("b.lan".into()),
+                    ..ScanEntry::test_open("10.0.0.1", 22)
+                },
+            ],
+            ..Default::default()
+        };
+        let baseline = Baseline::from_results(&results, Timestamps::default());
+        assert_eq!(baseline.ports.len(), 1);
+        let saved = serde_json::to_string(&baseline).unwrap();
+        assert_eq!(parse_baseline(&saved).unwrap(), baseline);
+        let from_results = parse_baseline(&serde_json::to_string(&results).unwrap()).unwrap();
+        assert_eq!(from_results.ports, baseline.ports);
+        assert_eq!(from_results.scanned_at, "2026-01-01T00:00:00Z");
+    }
+}
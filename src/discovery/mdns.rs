@@ -0,0 +1,172 @@
+//! mDNS / DNS-SD (Bonjour) browsing: enumerate advertised service types, then their instances.
+use crate::dns::{self, Record, RecordData};
+use anyhow::Result;
+use std::collections::{BTreeSet, HashMap};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::{self, Instant};
+
+pub const MDNS_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+pub const MDNS_PORT: u16 = 5353;
+/// Default total listen window for a discovery pass.
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(3);
+/// Meta-query that lists every service type advertised on the link.
+const SERVICES_META: &str = "_services._dns-sd._udp.local";
+
+/// One advertised service instance, resolved to an address and port.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MdnsService {
+    /// Instance name, e.g. `Office Printer`.
+    pub instance: String,
+    /// Service type without the domain, e.g. `_ipp._tcp`.
+    pub service_type: String,
+    /// Advertised host name, e.g. `printer.local`.
+    pub host: String,
+    pub ip: IpAddr,
+    pub port: u16,
+}
+
+impl MdnsService {
+    /// Short annotation for scan entries: `mdns _ipp._tcp "Office Printer"`.
+    pub fn describe(&self) -> String {
+        format!("mdns {} \"{}\"", self.service_type, self.instance)
+    }
+}
+
+/// Browse mDNS for up to `window`: the first third lists service types, the rest queries each type.
+///
+/// Queries are sent from an ephemeral port, so responders answer us directly (legacy unicast)
+/// and no multicast group membership is needed.
+pub async fn discover(window: Duration) -> Result<Vec<MdnsService>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.set_multicast_ttl_v4(255)?;
+    let group = SocketAddr::from((MDNS_GROUP, MDNS_PORT));
+    let deadline = Instant::now() + window;
+    let mut records = Vec::new();
+
+    socket
+        .send_to(
+            &dns::build_query(0, &[(SERVICES_META, dns::TYPE_PTR)], dns::CLASS_IN),
+            group,
+        )
+        .await?;
+    collect(&socket, Instant::now() + window / 3, &mut records).await;
+
+    let types = service_types(&records);
+    for ty in &types {
+        let query = dns::build_query(0, &[(ty.as_str(), dns::TYPE_PTR)], dns::CLASS_IN);
+        socket.send_to(&query, group).await?;
+    }
+    if !types.is_empty() {
+        collect(&socket, deadline, &mut records).await;
+    }
+    Ok(assemble(&records))
+}
+
+/// Gather records from every parseable response until `until`.
+async fn collect(socket: &UdpSocket, until: Instant, records: &mut Vec<Record>) {
+    let mut buf = [0u8; 9000];
+    while let Ok(Ok((n, _))) = time::timeout_at(until, socket.recv_from(&mut buf)).await {
+        if let Ok(msg) = dns::parse_message(&buf[..n]) {
+            records.extend(msg.records);
+        }
+    }
+}
+
+/// Service types named by answers to the `_services._dns-sd._udp` meta-query.
+fn service_types(records: &[Record]) -> BTreeSet<String> {
+    records
+        .iter()
+        .filter(|r| r.name.eq_ignore_ascii_case(SERVICES_META))
+        .filter_map(|r| match &r.data {
+            RecordData::Ptr(ty) => Some(ty.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Join SRV records with the A records of their targets. Instances without a known IPv4 address
+/// are dropped since there is nothing to scan.
+fn assemble(records: &[Record]) -> Vec<MdnsService> {
+    let mut addrs: HashMap<String, Ipv4Addr> = HashMap::new();
+    for r in records {
+        if let RecordData::A(ip) = r.data {
+            addrs.entry(r.name.to_ascii_lowercase()).or_insert(ip);
+        }
+    }
+    let mut out: Vec<MdnsService> = Vec::new();
+    for r in records {
+        let RecordData::Srv { port, target } = &r.data else {
+            continue;
+        };
+        let Some(ip) = addrs.get(&target.to_ascii_lowercase()) else {
+            continue;
+        };
+        let (instance, service_type) = split_instance(&r.name);
+        let svc = MdnsService {
+            instance,
+            service_type,
+            host: target.clone(),
+            ip: IpAddr::V4(*ip),
+            port: *port,
+        };
+        if !out.contains(&svc) {
+            out.push(svc);
+        }
+    }
+    out
+}
+
+/// `Office Printer._ipp._tcp.local` -> (`Office Printer`, `_ipp._tcp`).
+fn split_instance(full: &str) -> (String, String) {
+    let Some(idx) = full.find("._") else {
+        return (full.to_string(), String::new());
+    };
+    let ty = full[idx + 1..].trim_end_matches(".local");
+    (full[..idx].to_string(), ty.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rec(name: &str, data: RecordData) -> Record {
+        Record {
+            name: name.into(),
+            rtype: 0,
+            data,
+        }
+    }
+
+    #[test]
+    fn joins_srv_with_addresses() {
+        let records = vec![
+            rec(SERVICES_META, RecordData::Ptr("_ipp._tcp.local".into())),
+            rec(
+                "Office Printer._ipp._tcp.local",
+                RecordData::Srv {
+                    port: 631,
+                    target: "Printer.local".into(),
+                },
+            ),
+            rec("printer.local", RecordData::A([192, 168, 1, 20].into())),
+            rec(
+                "Lost._http._tcp.local",
+                RecordData::Srv {
+                    port: 80,
+                    target: "nowhere.local".into(),
+                },
+            ),
+        ];
+        assert_eq!(
+            service_types(&records).into_iter().collect::<Vec<_>>(),
+            ["_ipp._tcp.local"]
+        );
+        let found = assemble(&records);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].ip, IpAddr::V4([192, 168, 1, 20].into()));
+        assert_eq!(found[0].port, 631);
+        assert_eq!(found[0].describe(), "mdns _ipp._tcp \"Office Printer\"");
+    }
+}
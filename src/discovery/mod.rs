@@ -0,0 +1,4 @@
+//! Discovery passes that find hosts and services on the LAN before the port scan, by listening
+//! to what devices advertise rather than probing. Each pass has a bounded collection window.
+pub mod mdns;
+pub mod ssdp;
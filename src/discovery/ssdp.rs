@@ -0,0 +1,214 @@
+//! SSDP / UPnP discovery: multicast an M-SEARCH, collect `LOCATION` URLs, and read each device
+//! description for its type, manufacturer, and model.
+use crate::url::parse_url;
+use anyhow::{bail, Result};
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::task::JoinSet;
+use tokio::time::{self, Instant};
+
+pub const SSDP_GROUP: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
+pub const SSDP_PORT: u16 = 1900;
+/// Default window for collecting M-SEARCH responses.
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(3);
+/// Time allowed for fetching one device description.
+const DESCRIPTION_TIMEOUT: Duration = Duration::from_secs(2);
+/// Device descriptions are small; anything larger is truncated.
+const MAX_DESCRIPTION_BYTES: usize = 64 * 1024;
+
+/// A UPnP root device that answered the M-SEARCH.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SsdpDevice {
+    pub ip: IpAddr,
+    /// Description URL from the `LOCATION` header.
+    pub location: String,
+    /// Port of the description URL (the device's UPnP HTTP server).
+    pub port: u16,
+    /// `SERVER` header, e.g. `Linux/4.9 UPnP/1.0 Sonos/70.3`.
+    pub server: Option<String>,
+    pub device_type: Option<String>,
+    pub friendly_name: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+}
+
+impl SsdpDevice {
+    /// Short annotation for scan entries: `upnp MediaRenderer:1 Sonos "Play:1" (Kitchen)`.
+    pub fn describe(&self) -> String {
+        let mut out = String::from("upnp");
+        if let Some(ty) = &self.device_type {
+            // urn:schemas-upnp-org:device:MediaRenderer:1 -> MediaRenderer:1
+            out.push(' ');
+            out.push_str(ty.split(":device:").nth(1).unwrap_or(ty));
+        }
+        if let Some(m) = &self.manufacturer {
+            out.push(' ');
+            out.push_str(m);
+        }
+        if let Some(m) = &self.model {
+            out.push_str(&format!(" \"{m}\""));
+        }
+        if let Some(n) = &self.friendly_name {
+            out.push_str(&format!(" ({n})"));
+        }
+        out
+    }
+}
+
+/// Send an M-SEARCH, collect responses for `window`, then fetch each device description.
+pub async fn discover(window: Duration) -> Result<Vec<SsdpDevice>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.set_multicast_ttl_v4(2)?;
+    let mx = window.as_secs().clamp(1, 5);
+    let search = format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {SSDP_GROUP}:{SSDP_PORT}\r\nMAN: \"ssdp:discover\"\r\nMX: {mx}\r\nST: upnp:rootdevice\r\n\r\n"
+    );
+    socket
+        .send_to(search.as_bytes(), SocketAddr::from((SSDP_GROUP, SSDP_PORT)))
+        .await?;
+
+    let deadline = Instant::now() + window;
+    let mut seen = HashSet::new();
+    let mut found = Vec::new();
+    let mut buf = [0u8; 2048];
+    while let Ok(Ok((n, from))) = time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+        let text = String::from_utf8_lossy(&buf[..n]);
+        if let Some(device) = parse_response(&text, from.ip()) {
+            if seen.insert(device.location.clone()) {
+                found.push(device);
+            }
+        }
+    }
+
+    let mut fetches = JoinSet::new();
+    for device in found {
+        fetches.spawn(async move {
+            let mut device = device;
+            if let Ok(Ok(xml)) =
+                time::timeout(DESCRIPTION_TIMEOUT, fetch_description(&device)).await
+            {
+                device.device_type = xml_tag(&xml, "deviceType");
+                device.friendly_name = xml_tag(&xml, "friendlyName");
+                device.manufacturer = xml_tag(&xml, "manufacturer");
+                device.model = xml_tag(&xml, "modelName");
+            }
+            device
+        });
+    }
+    let mut devices = Vec::new();
+    while let Some(res) = fetches.join_next().await {
+        if let Ok(device) = res {
+            devices.push(device);
+        }
+    }
+    devices.sort_by_key(|d| (d.ip, d.port));
+    Ok(devices)
+}
+
+/// Parse an M-SEARCH response. Only `LOCATION`s on the responder's own address are accepted, so a
+/// device can't point discovery at some other host.
+fn parse_response(text: &str, from: IpAddr) -> Option<SsdpDevice> {
+    let mut lines = text.lines();
+    if !lines.next()?.starts_with("HTTP/1.1 200") {
+        return None;
+    }
+    let mut location = None;
+    let mut server = None;
+    for line in lines {
+        let Some((k, v)) = line.split_once(':') else {
+            continue;
+        };
+        match k.trim().to_ascii_lowercase().as_str() {
+            "location" => location = Some(v.trim().to_string()),
+            "server" => server = Some(v.trim().to_string()),
+            _ => {}
+        }
+    }
+    let location = location?;
+    let url = parse_url(&location).ok()?;
+    if url.host.parse::<IpAddr>().ok()? != from {
+        return None;
+    }
+    Some(SsdpDevice {
+        ip: from,
+        port: url.port,
+        location,
+        server,
+        device_type: None,
+        friendly_name: None,
+        manufacturer: None,
+        model: None,
+    })
+}
+
+/// GET the description XML over plain HTTP.
+async fn fetch_description(device: &SsdpDevice) -> Result<String> {
+    let url = parse_url(&device.location)?;
+    if url.tls {
+        bail!("https description URLs are not fetched");
+    }
+    let mut stream = TcpStream::connect((device.ip, url.port)).await?;
+    let req = format!(
+        "GET {} HTTP/1.1\r\nHost: {}:{}\r\nUser-Agent: lan-scan-rs\r\nConnection: close\r\n\r\n",
+        url.path, url.host, url.port
+    );
+    stream.write_all(req.as_bytes()).await?;
+    let mut body = Vec::new();
+    let mut chunk = [0u8; 4096];
+    while body.len() < MAX_DESCRIPTION_BYTES {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// Text of the first `<tag>...</tag>` element (namespace-agnostic enough for UPnP descriptions).
+fn xml_tag(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{tag}>");
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find("</")?;
+    let text = xml[start..end].trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_location_on_responder_only() {
+        let resp = "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=1800\r\nLocation: http://192.168.1.30:1400/xml/device_description.xml\r\nSERVER: Linux UPnP/1.0 Sonos/70.3\r\nST: upnp:rootdevice\r\n\r\n";
+        let from: IpAddr = "192.168.1.30".parse().unwrap();
+        let d = parse_response(resp, from).unwrap();
+        assert_eq!(d.port, 1400);
+        assert_eq!(d.server.as_deref(), Some("Linux UPnP/1.0 Sonos/70.3"));
+        assert!(parse_response(resp, "192.168.1.31".parse().unwrap()).is_none());
+    }
+
+    #[test]
+    fn describes_device_from_xml() {
+        let xml = "<root><device><deviceType>urn:schemas-upnp-org:device:ZonePlayer:1</deviceType>\
+                   <friendlyName>Kitchen</friendlyName><manufacturer>Sonos, Inc.</manufacturer>\
+                   <modelName>Play:1</modelName></device></root>";
+        let d = SsdpDevice {
+            ip: "192.168.1.30".parse().unwrap(),
+            location: String::new(),
+            port: 1400,
+            server: None,
+            device_type: xml_tag(xml, "deviceType"),
+            friendly_name: xml_tag(xml, "friendlyName"),
+            manufacturer: xml_tag(xml, "manufacturer"),
+            model: xml_tag(xml, "modelName"),
+        };
+        assert_eq!(
+            d.describe(),
+            "upnp ZonePlayer:1 Sonos, Inc. \"Play:1\" (Kitchen)"
+        );
+    }
+}
@@ -0,0 +1,291 @@
+//! Minimal DNS wire format: just enough to build queries and read answers for mDNS discovery
+//! and DNS probes. Only the record types we act on are decoded; everything else is kept raw.
+use anyhow::{bail, Result};
+
+pub const TYPE_A: u16 = 1;
+pub const TYPE_PTR: u16 = 12;
+pub const TYPE_TXT: u16 = 16;
+pub const TYPE_SRV: u16 = 33;
+pub const CLASS_IN: u16 = 1;
+pub const CLASS_CH: u16 = 3;
+
+/// Compression pointers followed before a name is declared malformed (loop protection).
+const MAX_POINTER_HOPS: usize = 32;
+
+/// Build a query with one question per `(name, qtype)`, all in `qclass`.
+pub fn build_query(id: u16, questions: &[(&str, u16)], qclass: u16) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(64);
+    buf.extend_from_slice(&id.to_be_bytes());
+    buf.extend_from_slice(&0u16.to_be_bytes()); // flags: standard query, no recursion
+    buf.extend_from_slice(&(questions.len() as u16).to_be_bytes());
+    buf.extend_from_slice(&[0, 0, 0, 0, 0, 0]); // an/ns/ar counts
+    for (name, qtype) in questions {
+        encode_name(&mut buf, name);
+        buf.extend_from_slice(&qtype.to_be_bytes());
+        buf.extend_from_slice(&qclass.to_be_bytes());
+    }
+    buf
+}
+
+/// Set the RD (recursion desired) bit on a query built by [`build_query`].
+pub fn set_recursion_desired(query: &mut [u8]) {
+    query[2] |= 0x01;
+}
+
+/// Response code from the header flags (0 = NOERROR, 5 = REFUSED, ...).
+pub fn rcode(flags: u16) -> u16 {
+    flags & 0x000F
+}
+
+/// Whether the server set RA (recursion available).
+pub fn recursion_available(flags: u16) -> bool {
+    flags & 0x0080 != 0
+}
+
+/// Whether a reply to a recursion-desired A query shows the server resolved it for us: recursion
+/// available, NOERROR, and at least one A record.
+pub fn resolved_recursively(msg: &Message) -> bool {
+    recursion_available(msg.flags)
+        && rcode(msg.flags) == 0
+        && msg.records.iter().any(|r| r.rtype == TYPE_A)
+}
+
+/// Append `name` as uncompressed labels (`a.b.local` -> `1a 1b 5local 0`).
+pub fn encode_name(buf: &mut Vec<u8>, name: &str) {
+    for label in name
+        .trim_end_matches('.')
+        .split('.')
+        .filter(|l| !l.is_empty())
+    {
+        let bytes = &label.as_bytes()[..label.len().min(63)];
+        buf.push(bytes.len() as u8);
+        buf.extend_from_slice(bytes);
+    }
+    buf.push(0);
+}
+
+/// A decoded resource record from any section of a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub name: String,
+    pub rtype: u16,
+    pub data: RecordData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordData {
+    A(std::net::Ipv4Addr),
+    Ptr(String),
+    Srv {
+        port: u16,
+        target: String,
+    },
+    /// TXT strings, each decoded lossily as UTF-8.
+    Txt(Vec<String>),
+    Other(Vec<u8>),
+}
+
+/// A parsed response: header id/flags plus the records of the answer, authority, and additional
+/// sections (mDNS responders put useful records in all three).
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub id: u16,
+    pub flags: u16,
+    pub records: Vec<Record>,
+}
+
+/// Parse a DNS message, skipping the question section.
+pub fn parse_message(buf: &[u8]) -> Result<Message> {
+    if buf.len() < 12 {
+        bail!("DNS message too short");
+    }
+    let id = read_u16(buf, 0)?;
+    let flags = read_u16(buf, 2)?;
+    let qd = read_u16(buf, 4)?;
+    let rr = read_u16(buf, 6)? as usize + read_u16(buf, 8)? as usize + read_u16(buf, 10)? as usize;
+    let mut pos = 12;
+    for _ in 0..qd {
+        let (_, next) = read_name(buf, pos)?;
+        pos = next + 4;
+    }
+    let mut records = Vec::with_capacity(rr);
+    for _ in 0..rr {
+        let (name, next) = read_name(buf, pos)?;
+        let rtype = read_u16(buf, next)?;
+        let rdlen = read_u16(buf, next + 8)? as usize;
+        let start = next + 10;
+        let end = start + rdlen;
+        if end > buf.len() {
+            bail!("DNS record overruns message");
+        }
+        let data = match rtype {
+            TYPE_A if rdlen == 4 => RecordData::A(std::net::Ipv4Addr::new(
+                buf[start],
+                buf[start + 1],
+                buf[start + 2],
+                buf[start + 3],
+            )),
+            TYPE_PTR => RecordData::Ptr(read_name(buf, start)?.0),
+            TYPE_SRV if rdlen >= 6 => RecordData::Srv {
+                port: read_u16(buf, start + 4)?,
+                target: read_name(buf, start + 6)?.0,
+            },
+            TYPE_TXT => RecordData::Txt(read_character_strings(&buf[start..end])),
+            _ => RecordData::Other(buf[start..end].to_vec()),
+        };
+        records.push(Record { name, rtype, data });
+        pos = end;
+    }
+    Ok(Message { id, flags, records })
+}
+
+/// Read a possibly compressed name at `pos`; returns it (without trailing dot) and the offset just
+/// past it in the original position.
+pub fn read_name(buf: &[u8], mut pos: usize) -> Result<(String, usize)> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut end = None;
+    let mut hops = 0;
+    loop {
+        let Some(&len) = buf.get(pos) else {
+            bail!("DNS name runs past end of message");
+        };
+        match len {
+            0 => {
+                end.get_or_insert(pos + 1);
+                break;
+            }
+            l if l & 0xC0 == 0xC0 => {
+                let Some(&lo) = buf.get(pos + 1) else {
+                    bail!("truncated DNS compression pointer");
+                };
+                end.get_or_insert(pos + 2);
+                hops += 1;
+                if hops > MAX_POINTER_HOPS {
+                    bail!("DNS compression loop");
+                }
+                pos = (((l & 0x3F) as usize) << 8) | lo as usize;
+            }
+            l => {
+                let l = l as usize;
+                let Some(label) = buf.get(pos + 1..pos + 1 + l) else {
+                    bail!("DNS label runs past end of message");
+                };
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                pos += 1 + l;
+            }
+        }
+    }
+    Ok((labels.join("."), end.unwrap_or(pos)))
+}
+
+fn read_character_strings(mut data: &[u8]) -> Vec<String> {
+    let mut out = Vec::new();
+    while let Some((&len, rest)) = data.split_first() {
+        let len = (len as usize).min(rest.len());
+        out.push(String::from_utf8_lossy(&rest[..len]).into_owned());
+        data = &rest[len..];
+    }
+    out
+}
+
+fn read_u16(buf: &[u8], pos: usize) -> Result<u16> {
+    match buf.get(pos..pos + 2) {
+        Some(b) => Ok(u16::from_be_bytes([b[0], b[1]])),
+        None => bail!("DNS message truncated"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Append a record header for `name_bytes` (already encoded) and the given rdata.
+    fn push_record(buf: &mut Vec<u8>, name_bytes: &[u8], rtype: u16, rdata: &[u8]) {
+        buf.extend_from_slice(name_bytes);
+        buf.extend_from_slice(&rtype.to_be_bytes());
+        buf.extend_from_slice(&CLASS_IN.to_be_bytes());
+        buf.extend_from_slice(&120u32.to_be_bytes());
+        buf.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        buf.extend_from_slice(rdata);
+    }
+
+    #[test]
+    fn parses_compressed_answers() {
+        let mut msg = vec![0, 0, 0x84, 0, 0, 0, 0, 3, 0, 0, 0, 0];
+        // PTR _ipp._tcp.local -> "Printer._ipp._tcp.local" (compressed suffix)
+        let owner_at = msg.len();
+        let mut owner = Vec::new();
+        encode_name(&mut owner, "_ipp._tcp.local");
+        let mut rdata = vec![7];
+        rdata.extend_from_slice(b"Printer");
+        rdata.extend_from_slice(&[0xC0, owner_at as u8]);
+        push_record(&mut msg, &owner, TYPE_PTR, &rdata);
+        // SRV for the instance, pointing at printer.local:631
+        let mut srv = vec![0, 0, 0, 0, 0x02, 0x77];
+        encode_name(&mut srv, "printer.local");
+        let mut inst = Vec::new();
+        encode_name(&mut inst, "Printer._ipp._tcp.local");
+        push_record(&mut msg, &inst, TYPE_SRV, &srv);
+        let mut host = Vec::new();
+        encode_name(&mut host, "printer.local");
+        push_record(&mut msg, &host, TYPE_A, &[192, 168, 1, 20]);
+
+        let parsed = parse_message(&msg).unwrap();
+        assert_eq!(
+            parsed.records[0].data,
+            RecordData::Ptr("Printer._ipp._tcp.local".into())
+        );
+        assert_eq!(
+            parsed.records[1].data,
+            RecordData::Srv {
+                port: 631,
+                target: "printer.local".into()
+            }
+        );
+        assert_eq!(
+            parsed.records[2].data,
+            RecordData::A([192, 168, 1, 20].into())
+        );
+    }
+
+    #[test]
+    fn header_flags_for_recursion_check() {
+        let mut query = build_query(7, &[("example.com", TYPE_A)], CLASS_IN);
+        assert_eq!(query[2], 0);
+        set_recursion_desired(&mut query);
+        assert_eq!(u16::from_be_bytes([query[2], query[3]]), 0x0100);
+
+        // REFUSED without RA, as a non-recursive or locked-down server answers.
+        assert_eq!(rcode(0x8105), 5);
+        assert!(!recursion_available(0x8105));
+
+        let answer = Record {
+            name: "example.com".into(),
+            rtype: TYPE_A,
+            data: RecordData::A([93, 184, 215, 14].into()),
+        };
+        let open = Message {
+            id: 7,
+            flags: 0x8180,
+            records: vec![answer.clone()],
+        };
+        assert!(resolved_recursively(&open));
+        let no_ra = Message {
+            flags: 0x8100,
+            ..open.clone()
+        };
+        assert!(!resolved_recursively(&no_ra));
+        let empty = Message {
+            records: Vec::new(),
+            ..open
+        };
+        assert!(!resolved_recursively(&empty));
+    }
+
+    #[test]
+    fn rejects_pointer_loops_and_truncation() {
+        let msg = [0u8, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0xC0, 12];
+        assert!(parse_message(&msg).is_err());
+        assert!(parse_message(&[0, 1, 2]).is_err());
+    }
+}
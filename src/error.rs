@@ -0,0 +1,23 @@
+//! Error type for the scanning API (`scanner`, `netdetect`), so library users can tell failure
+//! kinds apart; the binary still reports them through `anyhow`.
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ScanError {
+    /// A target spec that isn't an IP or CIDR, or a network over the size guard. `reason` is the
+    /// full message, naming the spec.
+    #[error("{reason}")]
+    InvalidTarget { spec: String, reason: String },
+    /// Nothing to scan: no targets (or pairs) were given, or the specs expanded to no hosts.
+    #[error("no targets to scan")]
+    NoTargets,
+    /// The cancellation token had already fired when the scan was started. A scan cancelled
+    /// while running still returns its partial results, with `cancelled` set.
+    #[error("scan cancelled before it started")]
+    Cancelled,
+    /// Reading network interfaces or another OS-level failure.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T, E = ScanError> = std::result::Result<T, E>;
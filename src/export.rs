@@ -0,0 +1,188 @@
+//! Report exports of `ScanResults` for download: CSV (one row per entry) and a flat XML document,
+//! plus an nmap `-p` port list for chaining into other tools.
+use crate::ports::format_ranges;
+use crate::types::{Protocol, ScanEntry, ScanResults};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+const CSV_HEADER: &str =
+    "ip,port,protocol,service,severity,latency_ms,host,label,advertised,banner,timestamp";
+
+/// One header row, then one row per entry; empty cells for absent fields.
+pub fn to_csv(results: &ScanResults) -> String {
+    let mut out = String::from(CSV_HEADER);
+    out.push_str("\r\n");
+    for e in &results.entries {
+        let row: Vec<String> = entry_fields(e)
+            .into_iter()
+            .map(|(_, value)| csv_field(value.as_deref().unwrap_or("")))
+            .collect();
+        out.push_str(&row.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// `<scan>` carrying the counters as attributes, with one `<entry>` per result.
+pub fn to_xml(results: &ScanResults) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!(
+        "<scan version=\"{}\" generated_at=\"{}\" scanned_total=\"{}\" scanned_done=\"{}\" \
+         open_count=\"{}\" cancelled=\"{}\">\n",
+        results.version,
+        xml_escape(&results.generated_at),
+        results.scanned_total,
+        results.scanned_done,
+        results.open_count,
+        results.cancelled
+    ));
+    for e in &results.entries {
+        out.push_str("  <entry");
+        for (name, value) in entry_fields(e) {
+            if let Some(value) = value {
+                out.push_str(&format!(" {name}=\"{}\"", xml_escape(&value)));
+            }
+        }
+        out.push_str("/>\n");
+    }
+    out.push_str("</scan>\n");
+    out
+}
+
+/// Open ports as an nmap `-p` argument: the union over all hosts on the first line, then with
+/// `per_host` one `<ip> <ports>` line per host. UDP findings switch to the `T:...,U:...` form.
+pub fn to_port_list(results: &ScanResults, per_host: bool) -> String {
+    let open: Vec<&ScanEntry> = results.entries.iter().filter(|e| e.open).collect();
+    let mut out = nmap_ports(&open);
+    out.push('\n');
+    if per_host {
+        let mut hosts: BTreeMap<(Option<IpAddr>, &str), Vec<&ScanEntry>> = BTreeMap::new();
+        for &e in &open {
+            hosts
+                .entry((e.ip.parse().ok(), e.ip.as_str()))
+                .or_default()
+                .push(e);
+        }
+        for ((_, ip), entries) in hosts {
+            out.push_str(&format!("{ip} {}\n", nmap_ports(&entries)));
+        }
+    }
+    out
+}
+
+fn nmap_ports(entries: &[&ScanEntry]) -> String {
+    let ports = |protocol: Protocol| -> Vec<u16> {
+        entries
+            .iter()
+            .filter(|e| e.protocol == protocol)
+            .map(|e| e.port)
+            .collect()
+    };
+    let (tcp, udp) = (ports(Protocol::Tcp), ports(Protocol::Udp));
+    if udp.is_empty() {
+        return format_ranges(&tcp);
+    }
+    let mut parts = Vec::new();
+    if !tcp.is_empty() {
+        parts.push(format!("T:{}", format_ranges(&tcp)));
+    }
+    parts.push(format!("U:{}", format_ranges(&udp)));
+    parts.join(",")
+}
+
+/// Exported columns in `CSV_HEADER` order.
+fn entry_fields(e: &ScanEntry) -> [(&'static str, Option<String>); 11] {
+    [
+        ("ip", Some(e.ip.clone())),
+        ("port", Some(e.port.to_string())),
+        ("protocol", Some(e.protocol.as_str().to_string())),
+        ("service", e.service.clone()),
+        ("severity", e.severity.map(|s| s.as_str().to_string())),
+        ("latency_ms", Some(e.latency_ms.to_string())),
+        ("host", e.host.clone()),
+        ("label", e.label.clone()),
+        ("advertised", e.advertised.clone()),
+        ("banner", e.banner.clone()),
+        ("timestamp", Some(e.timestamp.clone())),
+    ]
+}
+
+/// Quote a field (RFC 4180) when it holds a comma, quote, or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Not allowed in XML 1.0 at all.
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> ScanResults {
+        let mut e = ScanEntry::test_open("10.0.0.5", 80);
+        e.service = Some("http".into());
+        e.banner = Some("HTTP title=\"Intranet, v2\"".into());
+        ScanResults {
+            open_count: 1,
+            entries: vec![e],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn port_list_is_nmap_syntax_with_optional_per_host_lines() {
+        let mut results = sample();
+        results.entries.extend([
+            ScanEntry::test_open("10.0.0.5", 81),
+            ScanEntry::test_open("10.0.0.10", 22),
+        ]);
+        assert_eq!(to_port_list(&results, false), "22,80-81\n");
+        let mut snmp = ScanEntry::test_open("10.0.0.9", 161);
+        snmp.protocol = Protocol::Udp;
+        results.entries.push(snmp);
+        assert_eq!(
+            to_port_list(&results, true),
+            "T:22,80-81,U:161\n10.0.0.5 80-81\n10.0.0.9 U:161\n10.0.0.10 22\n"
+        );
+    }
+
+    #[test]
+    fn csv_quotes_fields_with_commas_and_quotes() {
+        let csv = to_csv(&sample());
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        assert_eq!(
+            lines.next(),
+            Some("10.0.0.5,80,tcp,http,,1,,,,\"HTTP title=\"\"Intranet, v2\"\"\",")
+        );
+    }
+
+    #[test]
+    fn xml_escapes_attributes_and_skips_absent_fields() {
+        let xml = to_xml(&sample());
+        assert!(xml.contains(
+            "<entry ip=\"10.0.0.5\" port=\"80\" protocol=\"tcp\" service=\"http\" \
+             latency_ms=\"1\" banner=\"HTTP title=&quot;Intranet, v2&quot;\" timestamp=\"\"/>"
+        ));
+        assert!(xml.trim_end().ends_with("</scan>"));
+    }
+}
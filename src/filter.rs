@@ -0,0 +1,95 @@
+//! Entry filters for results, as taken by `GET /api/results?service=http&port=443`.
+use serde::Deserialize;
+
+use crate::risk::Severity;
+use crate::types::{ScanEntry, ScanResults};
+
+/// Conditions an entry must all meet; unset fields match everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ResultFilter {
+    /// Service name (`http`, `ssh`, ...), compared case-insensitively.
+    pub service: Option<String>,
+    pub port: Option<u16>,
+    /// Address prefix: `192.168.1.` matches that /24, a full address one host.
+    pub ip: Option<String>,
+    /// Only entries rated at least this severity (unrated entries count as info).
+    pub min_severity: Option<Severity>,
+}
+
+impl ResultFilter {
+    pub fn matches(&self, e: &ScanEntry) -> bool {
+        let service_ok = self.service.as_deref().is_none_or(|want| {
+            e.service
+                .as_deref()
+                .is_some_and(|s| s.eq_ignore_ascii_case(want))
+        });
+        service_ok
+            && self.port.is_none_or(|p| e.port == p)
+            && self.ip.as_deref().is_none_or(|p| e.ip.starts_with(p))
+            && self
+                .min_severity
+                .is_none_or(|min| e.severity.unwrap_or(Severity::Info) >= min)
+    }
+}
+
+/// A copy of `results` keeping only the entries `f` matches. Counters and metadata still
+/// describe the whole scan.
+pub fn filter(results: &ScanResults, f: &ResultFilter) -> ScanResults {
+    ScanResults {
+        entries: results
+            .entries
+            .iter()
+            .filter(|e| f.matches(e))
+            .cloned()
+            .collect(),
+        ..results.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_combine_service_port_prefix_and_severity() {
+        let mut ssh = ScanEntry::test_open("192.168.1.5", 22);
+        ssh.service = Some("ssh".into());
+        let mut redis = ScanEntry::test_open("192.168.10.7", 6379);
+        redis.service = Some("redis".into());
+        redis.severity = Some(Severity::Critical);
+        let results = ScanResults {
+            entries: vec![ssh, redis],
+            open_count: 2,
+            ..Default::default()
+        };
+        let ports = |f: ResultFilter| -> Vec<u16> {
+            filter(&results, &f)
+                .entries
+                .iter()
+                .map(|e| e.port)
+                .collect()
+        };
+        let by_service = ResultFilter {
+            service: Some("SSH".into()),
+            ..Default::default()
+        };
+        assert_eq!(ports(by_service), [22]);
+        let by_prefix = ResultFilter {
+            ip: Some("192.168.1.".into()),
+            ..Default::default()
+        };
+        assert_eq!(ports(by_prefix), [22]);
+        let high = ResultFilter {
+            min_severity: Some(Severity::High),
+            port: Some(6379),
+            ..Default::default()
+        };
+        assert_eq!(ports(high), [6379]);
+        assert_eq!(ports(ResultFilter::default()), [22, 6379]);
+        let f = ResultFilter {
+            port: Some(80),
+            ..Default::default()
+        };
+        assert_eq!(filter(&results, &f).open_count, 2);
+    }
+}
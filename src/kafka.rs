@@ -0,0 +1,124 @@
+//! Kafka broker check for port 9092: an ApiVersions (key 18, v0) request, which every broker
+//! answers before authentication, confirms the protocol and hints at the broker's age.
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{self, Instant};
+
+pub const KAFKA_PORT: u16 = 9092;
+const API_VERSIONS: i16 = 18;
+const CORRELATION_ID: i32 = 0x4c53_4b31;
+const CLIENT_ID: &str = "lan-scan-rs";
+/// A v0 response lists ~60 APIs at 6 bytes each; anything far beyond that isn't Kafka.
+const MAX_RESPONSE: usize = 4096;
+
+/// What the broker's ApiVersions response says.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KafkaInfo {
+    /// Supported version range of ApiVersions itself.
+    pub api_min: i16,
+    pub api_max: i16,
+    /// Number of API keys the broker supports.
+    pub api_count: usize,
+}
+
+impl KafkaInfo {
+    /// Banner text: `kafka broker, api 0-3 (60 apis)`.
+    pub fn banner(&self) -> String {
+        format!(
+            "kafka broker, api {}-{} ({} apis)",
+            self.api_min, self.api_max, self.api_count
+        )
+    }
+}
+
+/// Send an ApiVersions v0 request and parse the answer.
+pub async fn probe(stream: &mut TcpStream, timeout: Duration) -> Option<KafkaInfo> {
+    time::timeout(timeout, stream.write_all(&api_versions_request()))
+        .await
+        .ok()?
+        .ok()?;
+    let deadline = Instant::now() + timeout;
+    let mut buf = Vec::with_capacity(512);
+    let mut tmp = [0u8; 1024];
+    loop {
+        if buf.len() >= 4 {
+            let size = i32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
+            let size = usize::try_from(size).ok().filter(|&s| s <= MAX_RESPONSE)?;
+            if buf.len() >= 4 + size {
+                return parse_response(&buf[4..4 + size]);
+            }
+        }
+        let n = time::timeout_at(deadline, stream.read(&mut tmp))
+            .await
+            .ok()?
+            .ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&tmp[..n]);
+    }
+}
+
+/// Size-prefixed request header with an empty v0 body.
+fn api_versions_request() -> Vec<u8> {
+    let mut body = Vec::with_capacity(32);
+    body.extend_from_slice(&API_VERSIONS.to_be_bytes());
+    body.extend_from_slice(&0i16.to_be_bytes()); // api version
+    body.extend_from_slice(&CORRELATION_ID.to_be_bytes());
+    body.extend_from_slice(&(CLIENT_ID.len() as i16).to_be_bytes());
+    body.extend_from_slice(CLIENT_ID.as_bytes());
+    let mut req = (body.len() as i32).to_be_bytes().to_vec();
+    req.extend_from_slice(&body);
+    req
+}
+
+/// Parse a v0 ApiVersions response (without its size prefix).
+fn parse_response(buf: &[u8]) -> Option<KafkaInfo> {
+    let i32_at = |i: usize| {
+        buf.get(i..i + 4)
+            .map(|b| i32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    let i16_at = |i: usize| buf.get(i..i + 2).map(|b| i16::from_be_bytes([b[0], b[1]]));
+    if i32_at(0)? != CORRELATION_ID || i16_at(4)? != 0 {
+        return None;
+    }
+    let count = usize::try_from(i32_at(6)?).ok()?;
+    if buf.len() < 10 + count * 6 {
+        return None;
+    }
+    (0..count).find_map(|k| {
+        let at = 10 + k * 6;
+        (i16_at(at)? == API_VERSIONS).then(|| KafkaInfo {
+            api_min: i16_at(at + 2).unwrap_or(0),
+            api_max: i16_at(at + 4).unwrap_or(0),
+            api_count: count,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_is_size_prefixed_api_versions_v0() {
+        let req = api_versions_request();
+        assert_eq!(&req[..4], &(req.len() as i32 - 4).to_be_bytes());
+        assert_eq!(&req[4..8], &[0, 18, 0, 0]);
+    }
+
+    #[test]
+    fn reads_api_versions_range_from_response() {
+        let mut resp = CORRELATION_ID.to_be_bytes().to_vec();
+        resp.extend_from_slice(&[0, 0, 0, 0, 0, 2]);
+        resp.extend_from_slice(&[0, 0, 0, 0, 0, 9]); // Produce 0-9
+        resp.extend_from_slice(&[0, 18, 0, 0, 0, 3]); // ApiVersions 0-3
+        let info = parse_response(&resp).unwrap();
+        assert_eq!(info.banner(), "kafka broker, api 0-3 (2 apis)");
+        // Wrong correlation id or a truncated array isn't Kafka.
+        assert_eq!(parse_response(&resp[..resp.len() - 1]), None);
+        resp[0] ^= 1;
+        assert_eq!(parse_response(&resp), None);
+    }
+}
@@ -1,6 +1,28 @@
 //! Library crate for lan-scan-rs exposing reusable modules.
+pub mod alert;
+pub mod amqp;
+pub mod config;
+pub mod diff;
+pub mod discovery;
+pub mod dns;
+pub mod error;
+pub mod export;
+pub mod filter;
+pub mod kafka;
 pub mod netdetect;
 pub mod ports;
+pub mod progress;
+pub mod proxy;
+pub mod resolve;
+pub mod risk;
+pub mod rng;
 pub mod scanner;
+pub mod schema;
 pub mod server;
+pub mod smb;
+pub mod snmp;
+pub mod source_port;
+pub mod syslog;
 pub mod types;
+mod url;
+pub mod webhook;
@@ -1,101 +1,126 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::net::{IpAddr, Ipv4Addr};
-use std::path::PathBuf;
-use std::time::Duration;
-
-use lan_scan_rs::types::ScanResults;
-use lan_scan_rs::{netdetect, ports, scanner, server};
-use std::fs::{self, File};
-use std::path::Path;
-
-use anyhow::Result;
-use clap::Parser;
-use ipnet::IpNet;
-
-/// lan-scan-rs — Fast, safe-by-default async LAN TCP port scanner with a tiny embedded web UI.
-#[derive(Debug, Clone, Parser)]
-#[command(
-    name = "lan-scan-rs",
-    version,
-    about = "Fast, safe-by-default async LAN TCP port scanner with a tiny embedded web UI.",
-    long_about = None
-)]
-struct Cli {
-    /// CIDR (e.g., 192.168.1.0/24) or path to file with CIDRs/IPs. If omitted, auto-detect local /24.
-    #[arg(long)]
-    targets: Option<String>,
-
-    /// Path to ports list file (one port or range per line).
-    #[arg(long, default_value = "ports.txt")]
-    ports: PathBuf,
-
-    /// Max concurrent TCP connect attempts.
-    #[arg(long, default_value_t = 1000)]
-    concurrency: usize,
-
-    /// Socket connect timeout in milliseconds.
-    #[arg(long = "timeout-ms", default_value_t = 400)]
-    timeout_ms: u64,
-
-    /// Write results as pretty JSON to this path (optional).
-    #[arg(long)]
-    output: Option<PathBuf>,
-
-    /// Start the embedded HTTP UI server (serves static UI; endpoints TBD).
-    #[arg(long = "serve-ui", default_value_t = false)]
-    serve_ui: bool,
-
-    /// Bind address for the HTTP UI server (only used with --serve-ui).
-    #[arg(long, default_value = "127.0.0.1:8080")]
-    bind: String,
-
-    /// Enable Redis PING probe on port 6379 (optional, off by default).
-    #[arg(long = "probe-redis", default_value_t = false)]
-    probe_redis: bool,
-
-    /// Quick scan preset (smaller port set, faster timeouts when unspecified).
-    #[arg(long, default_value_t = false)]
-    quick: bool,
-
-    /// Exclude specific ports (comma-separated list or ranges: e.g., 53,135-139).
-    #[arg(long = "exclude-ports")]
-    exclude_ports: Option<String>,
-}
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
-
-    println!("lan-scan-rs configuration:");
-    println!(
+use std::time::{Duration, Instant};
+
+use lan_scan_rs::discovery::{mdns, ssdp};
+use lan_scan_rs::ports::{PortOrder, PortsFallback};
+use lan_scan_rs::progress;
+use lan_scan_rs::risk::Severity;
+use lan_scan_rs::scanner::SharedProgress;
+use lan_scan_rs::syslog::SyslogTarget;
+use lan_scan_rs::types::{BaselineStatus, ScanEntry, ScanMeta, ScanPlan, ScanResults};
+use lan_scan_rs::webhook::WebhookConfig;
+use lan_scan_rs::{diff, netdetect, ports, resolve, rng, scanner, server, smb, source_port, types};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use time::OffsetDateTime;
+use tokio::time::MissedTickBehavior;
+use tokio_util::sync::CancellationToken;
+
+use anyhow::{Context, Result};
+
+#[macro_use]
+mod verbosity;
+mod cli;
+mod color;
+mod sinks;
+mod targets;
+use cli::Cli;
+use color::Palette;
+use lan_scan_rs::netdetect::TargetSet;
+use sinks::OutputSink;
+use targets::ParsedTargets;
+use verbosity::Verbosity;
+
+/// Exit codes: nothing to report, findings tripped a --fail-on-* gate, or the run failed.
+const EXIT_CLEAN: u8 = 0;
+const EXIT_FINDINGS: u8 = 1;
+const EXIT_ERROR: u8 = 2;
+
+fn main() -> ExitCode {
+    // Before the runtime starts its threads, or the local offset can't be read (--local-time).
+    types::init_local_offset();
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("failed to start the tokio runtime");
+    match runtime.block_on(run()) {
+        Ok(code) => ExitCode::from(code),
+        Err(e) => {
+            eprintln!("Error: {e:#}");
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+async fn run() -> Result<u8> {
+    let mut cli = cli::parse()?;
+    let mut exit_code = EXIT_CLEAN;
+    // JSON on stdout (`--output -`) must not be mixed with status lines.
+    verbosity::init(if cli.quiet || output_is_stdout(cli.output.as_deref()) {
+        Verbosity::Quiet
+    } else if cli.verbose {
+        Verbosity::Verbose
+    } else {
+        Verbosity::Normal
+    });
+
+    if cli.check_ports {
+        return check_ports_file(&cli.ports);
+    }
+
+    status!("lan-scan-rs configuration:");
+    status!(
         "  targets      : {}",
         cli.targets
             .as_deref()
             .unwrap_or("<auto-detect local IPv4 /24>")
     );
-    println!("  ports        : {}", cli.ports.display());
-    println!("  concurrency  : {}", cli.concurrency);
-    println!("  timeout_ms   : {}", cli.timeout_ms);
-    println!(
+    match cli.profile {
+        Some(profile) => status!("  ports        : profile {}", profile.as_str()),
+        None => status!("  ports        : {}", cli.ports.display()),
+    }
+    status!("  concurrency  : {}", cli.concurrency);
+    status!("  timeout_ms   : {}", cli.timeout_ms);
+    status!(
         "  output       : {}",
         cli.output
             .as_ref()
             .map(|p| p.display().to_string())
             .unwrap_or_else(|| "<none>".to_string())
     );
-    println!("  serve_ui     : {}", cli.serve_ui);
+    status!("  serve_ui     : {}", cli.serve_ui);
+    if cli.local_time {
+        match types::init_local_offset() {
+            Some(_) => status!(
+                "  timestamps   : local time (now {})",
+                types::TimestampFormat::Rfc3339.now_local()
+            ),
+            None => eprintln!(
+                "Warning: --local-time: this machine's UTC offset can't be determined; timestamps stay in UTC"
+            ),
+        }
+    }
+    // Settle the seed up front and print it, so a shuffled run can be replayed with --seed.
+    if cli.order == PortOrder::Random {
+        let seed = *cli.seed.get_or_insert_with(rng::entropy_seed);
+        status!("  seed         : {}", seed);
+    }
 
     // If no explicit targets were provided, detect local CIDRs and show a brief summary.
     if cli.targets.is_none() {
         match netdetect::detect_local_cidrs() {
             Ok(cidrs) => {
                 let mut total_ips = 0usize;
-                println!("Detected local IPv4 CIDRs:");
+                status!("Detected local IPv4 CIDRs:");
                 for cidr in &cidrs {
-                    let ips = netdetect::expand_cidr_to_ips(*cidr);
-                    total_ips += ips.len();
-                    println!("  - {} ({} hosts)", cidr, ips.len());
+                    let hosts = netdetect::iter_cidr_hosts(*cidr).len();
+                    total_ips += hosts;
+                    status!("  - {} ({} hosts)", cidr, hosts);
                 }
-                println!("Total targets (approx): {}", total_ips);
+                status!("Total targets (approx): {}", total_ips);
             }
             Err(e) => {
                 eprintln!("Warning: failed to detect local networks: {e}");
@@ -106,260 +131,1302 @@ async fn main() -> Result<()> {
     // Start embedded UI server if requested (non-blocking background task)
     if cli.serve_ui {
         let bind = cli.bind.clone();
+        let config = server::ServerConfig {
+            webhook: webhook_config(&cli),
+            ui_dir: cli.ui_dir.clone(),
+        };
         tokio::spawn(async move {
-            if let Err(e) = server::spawn_server(&bind).await {
+            if let Err(e) = server::spawn_server_with_config(&bind, config).await {
                 eprintln!("HTTP UI server error: {e}");
             }
         });
-        println!("UI server starting at http://{} (Ctrl+C to stop)", cli.bind);
+        status!("UI server starting at http://{} (Ctrl+C to stop)", cli.bind);
     }
 
     if !cli.serve_ui {
-        // Small demo: if targets == 127.0.0.1, run a quick scan to demonstrate engine.
-        if let Some(t) = cli.targets.as_deref() {
-            if t.trim() == "127.0.0.1" {
-                let targets = vec![IpAddr::V4(Ipv4Addr::LOCALHOST)];
-                // Keep demo ports small and fast
-                let demo_ports: Vec<u16> = vec![22, 80, 443, 8080];
-                println!(
-                    "\nRunning demo scan for 127.0.0.1 on ports {:?}...",
-                    demo_ports
-                );
-                let results = scanner::scan_targets_opts(
-                    &targets,
-                    &demo_ports,
-                    cli.concurrency.min(64),
-                    Duration::from_millis(cli.timeout_ms),
-                    cli.probe_redis,
-                )
-                .await?;
-                print_results_table(&results);
-                if let Some(path) = cli.output.as_deref() {
-                    if let Err(e) = write_results_json(path, &results) {
-                        eprintln!("Failed to write JSON to {}: {}", path.display(), e);
-                    } else {
-                        println!("Wrote JSON results to {}", path.display());
-                    }
+        if let Some(mut job) = plan_scan(&cli).await? {
+            if cli.dry_run {
+                if job.from_stdin {
+                    job.targets = targets::read_stdin_targets(min_prefix_len(&cli), resolver(&cli))
+                        .await
+                        .ips;
                 }
-            }
-        }
-
-        // If no demo override, run a full scan based on parsed targets and ports.
-        if cli.targets.is_some() && cli.targets.as_deref() != Some("127.0.0.1") {
-            let targets = parse_targets_arg(cli.targets.as_deref())?;
-            if targets.is_empty() {
-                eprintln!("No valid targets parsed. Exiting.");
+                let mut plan = ScanPlan::for_distinct(job.targets.hosts(), &job.ports);
+                if !job.pairs.is_empty() {
+                    plan.total_connects = job.pairs.len() as u64;
+                }
+                print_plan(&plan);
             } else {
-                let mut ports_list = if cli.quick {
-                    ports::quick_ports()
+                if job.from_stdin && cli.every.is_some() {
+                    anyhow::bail!("--every cannot be combined with targets from stdin");
+                }
+                if cli.retry_empty_hosts > 0 && !job.pairs.is_empty() {
+                    eprintln!("Warning: --retry-empty-hosts ignored with --pairs");
+                }
+                if (cli.mdns || cli.ssdp) && job.from_stdin {
+                    eprintln!("Warning: discovery (--mdns/--ssdp) ignored with targets from stdin");
+                } else if (cli.mdns || cli.ssdp) && !job.pairs.is_empty() {
+                    eprintln!("Warning: discovery (--mdns/--ssdp) ignored with --pairs");
                 } else {
-                    ports::load_ports_or_default(&cli.ports)
-                };
-                if let Some(ex) = &cli.exclude_ports {
-                    if let Ok(exv) = ports::parse_ports_str(ex) {
-                        ports_list.retain(|p| !exv.contains(p));
+                    if cli.mdns {
+                        add_mdns_services(&mut job).await;
                     }
-                }
-                println!(
-                    "Starting scan: {} hosts x {} ports = {} sockets",
-                    targets.len(),
-                    ports_list.len(),
-                    targets.len() * ports_list.len()
-                );
-                let results = scanner::scan_targets_opts(
-                    &targets,
-                    &ports_list,
-                    cli.concurrency,
-                    Duration::from_millis(cli.timeout_ms),
-                    cli.probe_redis,
-                )
-                .await?;
-                print_results_table(&results);
-                if let Some(path) = cli.output.as_deref() {
-                    if let Err(e) = write_results_json(path, &results) {
-                        eprintln!("Failed to write JSON to {}: {}", path.display(), e);
-                    } else {
-                        println!("Wrote JSON results to {}", path.display());
+                    if cli.ssdp {
+                        add_ssdp_devices(&mut job).await;
                     }
                 }
-            }
-        } else if cli.targets.is_none() {
-            // Auto-detect and scan defaults if no targets provided.
-            match netdetect::detect_local_cidrs() {
-                Ok(cidrs) => {
-                    let mut targets_all: Vec<IpAddr> = Vec::new();
-                    for cidr in &cidrs {
-                        targets_all.extend(netdetect::expand_cidr_to_ips(*cidr));
-                    }
-                    let mut ports_list = if cli.quick {
-                        ports::quick_ports()
+                if cli.auto_timeout {
+                    if cli.proxy.is_some() {
+                        // Direct RTTs say nothing about connects that go through the proxy.
+                        eprintln!("Warning: --auto-timeout ignored with --proxy");
+                    } else if job.from_stdin {
+                        eprintln!(
+                            "Warning: --auto-timeout needs targets up front; ignored with stdin"
+                        );
                     } else {
-                        ports::load_ports_or_default(&cli.ports)
-                    };
-                    if let Some(ex) = &cli.exclude_ports {
-                        if let Ok(exv) = ports::parse_ports_str(ex) {
-                            ports_list.retain(|p| !exv.contains(p));
-                        }
+                        auto_calibrate(&mut job).await;
                     }
-                    println!(
-                        "Starting scan: {} hosts x {} ports = {} sockets",
-                        targets_all.len(),
-                        ports_list.len(),
-                        targets_all.len() * ports_list.len()
-                    );
-                    let results = scanner::scan_targets_opts(
-                        &targets_all,
-                        &ports_list,
-                        cli.concurrency,
-                        Duration::from_millis(if cli.quick {
-                            cli.timeout_ms.min(250)
-                        } else {
-                            cli.timeout_ms
-                        }),
-                        cli.probe_redis,
-                    )
-                    .await?;
-                    print_results_table(&results);
-                    if let Some(path) = cli.output.as_deref() {
-                        if let Err(e) = write_results_json(path, &results) {
-                            eprintln!("Failed to write JSON to {}: {}", path.display(), e);
-                        } else {
-                            println!("Wrote JSON results to {}", path.display());
+                }
+                match cli.every {
+                    Some(every) => run_every(&cli, job, every).await,
+                    None => {
+                        let cancel = CancellationToken::new();
+                        let results = run_scan(&cli, job, cancel, cli.output.as_deref()).await?;
+                        if findings_gate_tripped(&cli, &results)? {
+                            exit_code = EXIT_FINDINGS;
                         }
                     }
                 }
-                Err(e) => eprintln!("Failed to detect local networks: {e}"),
             }
         }
     }
 
     // If UI is running, keep the process alive until Ctrl+C.
     if cli.serve_ui {
-        println!("Press Ctrl+C to stop the server...");
+        status!("Press Ctrl+C to stop the server...");
+        let _ = tokio::signal::ctrl_c().await;
+    }
+
+    Ok(exit_code)
+}
+
+/// Whether --fail-on-open / --fail-on-new should turn these results into a non-zero exit.
+fn findings_gate_tripped(cli: &Cli, results: &ScanResults) -> Result<bool> {
+    if cli.fail_on_open && results.open_count > 0 {
+        eprintln!("Failing: {} open port(s) found", results.open_count);
+        return Ok(true);
+    }
+    if let Some(path) = &cli.fail_on_new {
+        let baseline = diff::load_baseline(path)?;
+        let new = diff::new_open_entries(&baseline, results);
+        if !new.is_empty() {
+            eprintln!(
+                "Failing: {} port(s) open that are not in the baseline:",
+                new.len()
+            );
+            for e in &new {
+                eprintln!("  {}:{}", e.ip, e.port);
+            }
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// A fully resolved scan: what to connect to and how.
+#[derive(Clone)]
+struct ScanJob {
+    targets: TargetSet,
+    ports: Vec<u16>,
+    options: scanner::ScanOptions,
+    /// Targets are streamed from stdin while scanning; `targets` starts out empty.
+    from_stdin: bool,
+    /// `--pairs`: scan exactly these sockets. `targets`/`ports` then hold the distinct hosts and
+    /// ports among them.
+    pairs: Vec<(IpAddr, u16)>,
+}
+
+/// Resolve targets, ports, and options from the CLI. Returns `None` when there is nothing to scan.
+async fn plan_scan(cli: &Cli) -> Result<Option<ScanJob>> {
+    if let Some(path) = &cli.pairs {
+        return plan_pairs(cli, path);
+    }
+    // Small demo: if targets == 127.0.0.1, run a quick scan to demonstrate engine.
+    if cli.targets.as_deref().map(str::trim) == Some("127.0.0.1") {
+        // Keep demo ports small and fast, unless ports were given inline
+        let demo_ports: Vec<u16> = inline_ports(cli)?.unwrap_or_else(|| vec![22, 80, 443, 8080]);
+        status!(
+            "\nRunning demo scan for 127.0.0.1 on ports {:?}...",
+            demo_ports
+        );
+        let mut options = scan_options(cli, Duration::from_millis(cli.timeout_ms), HashMap::new());
+        options.concurrency = cli.concurrency.min(64);
+        return Ok(Some(ScanJob {
+            targets: [IpAddr::V4(Ipv4Addr::LOCALHOST)].into_iter().collect(),
+            ports: demo_ports,
+            options,
+            from_stdin: false,
+            pairs: Vec::new(),
+        }));
+    }
+
+    let from_stdin = targets::is_stdin_spec(cli.stdin, cli.targets.as_deref());
+    let (parsed, timeout_ms) = if from_stdin {
+        (ParsedTargets::default(), cli.timeout_ms)
+    } else if cli.targets.is_some() {
+        let parsed =
+            targets::parse_targets_arg(cli.targets.as_deref(), min_prefix_len(cli), &resolver(cli))
+                .await?;
+        if parsed.ips.is_empty() {
+            eprintln!("No valid targets parsed. Exiting.");
+            return Ok(None);
+        }
+        (parsed, cli.timeout_ms)
+    } else {
+        // Auto-detect and scan defaults if no targets provided.
+        match netdetect::detect_local_cidrs() {
+            Ok(cidrs) => {
+                let mut targets_all = TargetSet::default();
+                for cidr in &cidrs {
+                    targets_all.push_net(*cidr);
+                }
+                let timeout_ms = if cli.quick {
+                    cli.timeout_ms.min(250)
+                } else {
+                    cli.timeout_ms
+                };
+                let parsed = ParsedTargets {
+                    ips: targets_all,
+                    ..Default::default()
+                };
+                (parsed, timeout_ms)
+            }
+            Err(e) => {
+                eprintln!("Failed to detect local networks: {e}");
+                return Ok(None);
+            }
+        }
+    };
+
+    let mut ports_list = if let Some(inline) = inline_ports(cli)? {
+        inline
+    } else if let Some(profile) = cli.profile {
+        ports::profile_preset(profile).0
+    } else if cli.quick {
+        ports::quick_ports()
+    } else {
+        load_ports_file(&cli.ports, cli.ports_fallback)?
+    };
+    if let Some(ex) = &cli.exclude_ports {
+        let exv = ports::parse_ports_list(ex)
+            .map_err(|e| anyhow::anyhow!("invalid --exclude-ports: {e:#}"))?;
+        ports_list.retain(|p| !exv.contains(p));
+    }
+    let mut options = scan_options(cli, Duration::from_millis(timeout_ms), parsed.names);
+    options.host_labels = parsed.labels;
+    Ok(Some(ScanJob {
+        targets: parsed.ips,
+        ports: ports_list,
+        options,
+        from_stdin,
+        pairs: Vec::new(),
+    }))
+}
+
+/// A job for `--pairs`: only the listed sockets are connected to.
+fn plan_pairs(cli: &Cli, path: &Path) -> Result<Option<ScanJob>> {
+    let pairs = targets::load_pairs_file(path)?;
+    if pairs.is_empty() {
+        eprintln!("No ip:port pairs in {}. Exiting.", path.display());
+        return Ok(None);
+    }
+    let hosts = pairs.iter().map(|&(ip, _)| ip).collect();
+    let mut seen = HashSet::new();
+    let ports = pairs
+        .iter()
+        .map(|&(_, port)| port)
+        .filter(|port| seen.insert(*port))
+        .collect();
+    Ok(Some(ScanJob {
+        targets: hosts,
+        ports,
+        options: scan_options(cli, Duration::from_millis(cli.timeout_ms), HashMap::new()),
+        from_stdin: false,
+        pairs,
+    }))
+}
+
+/// Browse mDNS and fold the advertised services into the job.
+async fn add_mdns_services(job: &mut ScanJob) {
+    let window = mdns::DEFAULT_WINDOW;
+    status!("Browsing mDNS for {}s...", window.as_secs());
+    match mdns::discover(window).await {
+        Ok(services) => {
+            status!("  mdns         : {} service(s) advertised", services.len());
+            add_discovered(job, services.iter().map(|s| (s.ip, s.port, s.describe())));
+        }
+        Err(e) => eprintln!("Warning: mDNS discovery failed: {e:#}"),
+    }
+}
+
+/// Run an SSDP M-SEARCH and fold the responding UPnP devices into the job.
+async fn add_ssdp_devices(job: &mut ScanJob) {
+    let window = ssdp::DEFAULT_WINDOW;
+    status!("Searching SSDP/UPnP for {}s...", window.as_secs());
+    match ssdp::discover(window).await {
+        Ok(devices) => {
+            status!("  ssdp         : {} device(s) responded", devices.len());
+            add_discovered(job, devices.iter().map(|d| (d.ip, d.port, d.describe())));
+        }
+        Err(e) => eprintln!("Warning: SSDP discovery failed: {e:#}"),
+    }
+}
+
+/// Fold discovered `(ip, port, description)` services into the job. Only hosts already among the
+/// targets are kept; each description annotates its entry, and a port missing from the port list
+/// is scanned on the host that advertised it.
+fn add_discovered(job: &mut ScanJob, found: impl Iterator<Item = (IpAddr, u16, String)>) {
+    for (ip, port, description) in found {
+        if !job.targets.contains(ip) {
+            status!("    {ip}:{port} {description} (outside targets, skipped)");
+            continue;
+        }
+        status!("    {ip}:{port} {description}");
+        job.options
+            .advertised
+            .entry((ip, port))
+            .or_insert(description);
+    }
+}
+
+/// Replace the job's connect timeout with one calibrated from measured RTTs, if any host answers.
+async fn auto_calibrate(job: &mut ScanJob) {
+    match scanner::calibrate_timeout(job.targets.hosts()).await {
+        Some(c) => {
+            status!(
+                "  timeout_ms   : {} (auto: {}x p95 RTT {} ms over {} samples)",
+                c.timeout.as_millis(),
+                scanner::CALIBRATION_FACTOR,
+                c.p95_rtt.as_millis(),
+                c.samples
+            );
+            job.options.timeout = c.timeout;
+        }
+        None => status!(
+            "  timeout_ms   : {} (auto: no responsive hosts, using configured)",
+            job.options.timeout.as_millis()
+        ),
+    }
+}
+
+/// Ports from the `--ports` file. The default `ports.txt` may be absent (`--ports-fallback` is
+/// scanned), but a file named explicitly must exist; an empty file falls back with a warning.
+/// With `--ports-fallback none` either case is an error.
+fn load_ports_file(path: &Path, fallback: PortsFallback) -> Result<Vec<u16>> {
+    if !path.exists() {
+        if path == Path::new(cli::DEFAULT_PORTS_FILE) {
+            return fallback.ports().with_context(|| {
+                format!(
+                    "ports file not found: {} (--ports-fallback none)",
+                    path.display()
+                )
+            });
+        }
+        anyhow::bail!(
+            "ports file not found: {} (use --port/--ports-inline for a list, or omit --ports for \
+             the built-in set)",
+            path.display()
+        );
+    }
+    let list = ports::load_ports_from_path(path)
+        .map_err(|e| anyhow::anyhow!("invalid ports file {}: {e:#}", path.display()))?;
+    if list.is_empty() {
+        let fallback_list = fallback.ports().with_context(|| {
+            format!(
+                "ports file {} lists no ports (--ports-fallback none)",
+                path.display()
+            )
+        })?;
+        let set = match fallback {
+            PortsFallback::Quick => "quick",
+            _ => "built-in default",
+        };
+        eprintln!(
+            "Warning: ports file {} lists no ports; scanning the {set} set",
+            path.display()
+        );
+        return Ok(fallback_list);
+    }
+    Ok(list)
+}
+
+/// `--check-ports`: report on the ports file without scanning. Bad lines make it an error exit.
+fn check_ports_file(path: &Path) -> Result<u8> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read ports file: {}", path.display()))?;
+    let report = ports::check_ports_str(&content);
+    println!(
+        "Ports file {}: {} port(s), {} duplicate(s) collapsed",
+        path.display(),
+        report.ports.len(),
+        report.duplicates
+    );
+    if !report.ports.is_empty() {
+        println!("  ports : {}", ports::format_ranges(&report.ports));
+    }
+    if report.errors.is_empty() {
+        println!("  OK");
+        return Ok(EXIT_CLEAN);
+    }
+    println!("  {} bad line(s):", report.errors.len());
+    for error in &report.errors {
+        println!("    {error}");
+    }
+    Ok(EXIT_ERROR)
+}
+
+/// Ports given directly on the command line (`--port` and `--ports-inline`), if any.
+fn inline_ports(cli: &Cli) -> Result<Option<Vec<u16>>> {
+    if cli.port.is_empty() && cli.ports_inline.is_none() {
+        return Ok(None);
+    }
+    let mut list = cli.port.clone();
+    if let Some(inline) = &cli.ports_inline {
+        let parsed = ports::parse_ports_list(inline)
+            .map_err(|e| anyhow::anyhow!("invalid --ports-inline: {e:#}"))?;
+        list.extend(parsed);
+    }
+    let mut seen = std::collections::HashSet::new();
+    list.retain(|p| seen.insert(*p));
+    Ok(Some(list))
+}
+
+/// Run a planned scan, then print the table, write JSON to `output` if given, and notify the webhook.
+async fn run_scan(
+    cli: &Cli,
+    job: ScanJob,
+    cancel: CancellationToken,
+    output: Option<&Path>,
+) -> Result<ScanResults> {
+    if job.from_stdin {
+        status!(
+            "Starting scan: targets from stdin x {} ports",
+            job.ports.len()
+        );
+    } else if !job.pairs.is_empty() {
+        status!(
+            "Starting scan: {} ip:port pairs on {} hosts",
+            job.pairs.len(),
+            job.targets.len()
+        );
+    } else {
+        status!(
+            "Starting scan: {} hosts x {} ports = {} sockets",
+            job.targets.len(),
+            job.ports.len(),
+            job.targets.len() * job.ports.len()
+        );
+    }
+    let planned = if job.from_stdin {
+        None
+    } else if !job.pairs.is_empty() {
+        Some(job.pairs.len() as u64)
+    } else {
+        Some(job.targets.len() as u64 * job.ports.len() as u64)
+    };
+    if let Some(total) = planned {
+        report_worst_case(cli, total, &job.options);
+    }
+    let probes = active_probes(&job.options);
+    detail!(
+        "  probes       : {}",
+        if probes.is_empty() {
+            "none".to_string()
+        } else {
+            probes.join(", ")
+        }
+    );
+    let mut options = job.options;
+    let baseline = match cli.baseline.as_deref().map(diff::load_baseline) {
+        Some(Ok(baseline)) => Some(baseline),
+        Some(Err(e)) => {
+            eprintln!("Warning: {e:#}; results not compared against the baseline");
+            None
+        }
+        None => None,
+    };
+    let sinks: sinks::Sinks = Arc::new(std::sync::Mutex::new(
+        output_sinks(cli, output, baseline.clone()).await,
+    ));
+    // Before the clones, so findings of the SNMP sweep and retry passes reach the sinks too.
+    options.on_entry = Some(sinks::entry_callback(&sinks));
+    let snmp_options = (!cli.snmp_communities.is_empty()).then(|| options.clone());
+    // A retry pass scans hosts x ports, which a pair list exists to avoid.
+    let retry_options =
+        (cli.retry_empty_hosts > 0 && job.pairs.is_empty()).then(|| options.clone());
+    let pairs_spec = cli.pairs.as_ref().map(|p| format!("pairs:{}", p.display()));
+    let target_spec = if job.from_stdin {
+        "stdin"
+    } else if let Some(spec) = &pairs_spec {
+        spec
+    } else {
+        cli.targets.as_deref().unwrap_or("auto")
+    };
+    let stamps = options.timestamps();
+    let mut meta = ScanMeta::begin(
+        target_spec,
+        options.concurrency,
+        options.timeout,
+        job.ports.len(),
+        stamps,
+    );
+    let shared = SharedProgress::new();
+    let done = CancellationToken::new();
+    let reporter = (verbosity::level() > Verbosity::Quiet)
+        .then(|| tokio::spawn(report_progress(shared.clone(), done.clone())));
+    let started = Instant::now();
+    let sweep_cancel = cancel.clone();
+    let (results, stdin_targets) = if job.from_stdin {
+        let (stream, read) = targets::stream_stdin_targets(min_prefix_len(cli), resolver(cli));
+        let results =
+            scanner::scan_stream_shared(stream, &job.ports, options, cancel, shared).await;
+        // Empty if the scan was cancelled before stdin reached EOF.
+        (results, read.await.unwrap_or_default())
+    } else if !job.pairs.is_empty() {
+        let results = scanner::scan_pairs_shared(&job.pairs, options, cancel, shared).await;
+        (results, ParsedTargets::default())
+    } else {
+        // Networks are expanded as the scan pulls hosts, never listed in full.
+        let stream = tokio_stream::iter(job.targets.hosts());
+        let results =
+            scanner::scan_stream_shared(stream, &job.ports, options, cancel, shared).await;
+        (results, ParsedTargets::default())
+    };
+    done.cancel();
+    if let Some(reporter) = reporter {
+        let _ = reporter.await;
+    }
+    let mut results = results?;
+    // Stdin labels are only known once their lines were read; attach them afterwards.
+    for e in &mut results.entries {
+        if let Some(label) =
+            e.ip.parse()
+                .ok()
+                .and_then(|ip| stdin_targets.labels.get(&ip))
+        {
+            e.label.get_or_insert_with(|| label.clone());
+        }
+    }
+    let targets = if job.from_stdin {
+        &stdin_targets.ips
+    } else {
+        &job.targets
+    };
+    // Only the retry and SNMP passes need the hosts as a list.
+    let hosts: Vec<IpAddr> = if retry_options.is_some() || snmp_options.is_some() {
+        targets.hosts().collect()
+    } else {
+        Vec::new()
+    };
+    if let Some(mut retry_options) = retry_options.filter(|_| !results.cancelled) {
+        // Retry passes share what is left of --max-duration.
+        retry_options.max_duration = retry_options
+            .max_duration
+            .map(|limit| limit.saturating_sub(started.elapsed()));
+        let found = results.open_count;
+        scanner::retry_empty_hosts(
+            &mut results,
+            &hosts,
+            &job.ports,
+            &retry_options,
+            cli.retry_empty_hosts,
+            &sweep_cancel,
+        )
+        .await;
+        status!(
+            "Retried hosts without open ports: {} more open port(s)",
+            results.open_count - found
+        );
+    }
+    if let Some(snmp_options) = snmp_options.filter(|_| !results.cancelled) {
+        status!("Checking SNMP communities on {} host(s)...", hosts.len());
+        scanner::snmp_sweep(
+            &mut results,
+            &hosts,
+            &cli.snmp_communities,
+            &snmp_options,
+            &sweep_cancel,
+        )
+        .await;
+    }
+    meta.finish(started.elapsed(), stamps);
+    results.meta = Some(meta);
+    if results.timed_out {
+        status!(
+            "\nScan stopped at --max-duration: keeping partial results ({} of {} sockets scanned)",
+            results.scanned_done,
+            results.scanned_total
+        );
+    } else if results.cancelled {
+        status!(
+            "\nScan cancelled: keeping partial results ({} of {} sockets scanned)",
+            results.scanned_done,
+            results.scanned_total
+        );
+    }
+    if let Some(baseline) = &baseline {
+        diff::annotate(baseline, &mut results);
+    }
+    sinks::finish(&sinks, &results).await;
+    Ok(results)
+}
+
+/// Print how long the scan takes if every connect times out, warning when that is more than
+/// --max-duration allows.
+fn report_worst_case(cli: &Cli, total: u64, options: &scanner::ScanOptions) {
+    let worst = progress::estimate_worst_case(total, options.concurrency, options.timeout);
+    status!(
+        "  worst case   : {} ({} connects, {} in flight, {} ms timeout)",
+        progress::format_eta(worst.as_secs()),
+        total,
+        options.concurrency,
+        options.timeout.as_millis()
+    );
+    if let Some(limit) = cli.max_duration.filter(|limit| worst > *limit) {
+        eprintln!(
+            "Warning: worst case {} exceeds --max-duration {}; raise --concurrency or lower \
+             --timeout-ms to finish in time",
+            progress::format_eta(worst.as_secs()),
+            progress::format_eta(limit.as_secs())
+        );
+    }
+}
+
+/// Re-run the scan every `every` until Ctrl-C, writing one timestamped JSON file per run
+/// (named after --output, default `results.json`) and logging what changed since the last run.
+async fn run_every(cli: &Cli, job: ScanJob, every: Duration) {
+    let stop = CancellationToken::new();
+    let stop_ctrlc = stop.clone();
+    tokio::spawn(async move {
         let _ = tokio::signal::ctrl_c().await;
+        stop_ctrlc.cancel();
+    });
+    let base = cli
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("results.json"));
+    status!("Scanning every {}s (Ctrl+C to stop)", every.as_secs());
+
+    let mut ticker = tokio::time::interval(every);
+    // A run that outlasts the interval swallows the ticks it missed instead of queueing them.
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut previous: Option<ScanResults> = None;
+    loop {
+        tokio::select! {
+            _ = stop.cancelled() => break,
+            _ = ticker.tick() => {}
+        }
+        let path = timestamped_path(&base, OffsetDateTime::now_utc());
+        match run_scan(cli, job.clone(), stop.child_token(), Some(&path)).await {
+            // A cancelled run never reached some ports; diffing it would report them as closed,
+            // so it is neither logged nor kept as the next comparison point.
+            Ok(results) if results.cancelled => {
+                status!("Run cancelled before finishing; not comparing with the previous run")
+            }
+            Ok(results) => {
+                if let Some(prev) = &previous {
+                    log_changes(prev, &results);
+                }
+                previous = Some(results);
+            }
+            Err(e) => eprintln!("Scheduled scan failed: {e:#}"),
+        }
+        if stop.is_cancelled() {
+            break;
+        }
+    }
+    status!("Stopped scheduled scans");
+}
+
+/// `results.json` -> `results-20260102T030405Z.json`.
+fn timestamped_path(base: &Path, at: OffsetDateTime) -> PathBuf {
+    if output_is_stdout(Some(base)) {
+        return base.to_path_buf();
+    }
+    let stamp = format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        at.year(),
+        u8::from(at.month()),
+        at.day(),
+        at.hour(),
+        at.minute(),
+        at.second()
+    );
+    let stem = base
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "results".to_string());
+    let name = match base.extension() {
+        Some(ext) => format!("{stem}-{stamp}.{}", ext.to_string_lossy()),
+        None => format!("{stem}-{stamp}"),
+    };
+    base.with_file_name(name)
+}
+
+/// One sink per output flag, in the order they report: table, JSON, baseline, ports summary,
+/// then syslog and the webhook.
+async fn output_sinks(
+    cli: &Cli,
+    output: Option<&Path>,
+    baseline: Option<diff::Baseline>,
+) -> Vec<Box<dyn OutputSink>> {
+    let mut out: Vec<Box<dyn OutputSink>> = Vec::new();
+    // Quiet runs that write JSON keep stdout empty (or JSON-only for `--output -`).
+    let json_only = output_is_stdout(output) || (cli.quiet && output.is_some());
+    if !json_only {
+        out.push(Box::new(sinks::TableSink {
+            view: TableView {
+                min_severity: cli.min_severity,
+                group_by_host: cli.group_by_host,
+                palette: Palette::detect(cli.no_color),
+            },
+        }));
+    }
+    if let Some(path) = output {
+        out.push(Box::new(sinks::JsonSink {
+            path: path.to_path_buf(),
+        }));
     }
+    if let Some(path) = &cli.save_baseline {
+        out.push(Box::new(sinks::BaselineSink {
+            path: path.clone(),
+            stamps: types::Timestamps {
+                format: cli.timestamp_format,
+                local: cli.local_time,
+            },
+        }));
+    }
+    if let Some(path) = &cli.ports_summary {
+        out.push(Box::new(sinks::PortsSummarySink {
+            path: path.clone(),
+            per_host: cli.ports_summary_per_host,
+        }));
+    }
+    if let Some(target) = syslog_target(cli) {
+        if let Some(sink) = sinks::SyslogSink::connect(&target).await {
+            out.push(Box::new(sink));
+        }
+    }
+    if let Some(hook) = webhook_config(cli) {
+        out.push(Box::new(sinks::WebhookSink::new(hook, baseline)));
+    }
+    out
+}
+
+/// Print ports that opened or closed between two runs.
+fn log_changes(previous: &ScanResults, current: &ScanResults) {
+    let baseline = |results| diff::Baseline::from_results(results, types::Timestamps::default());
+    let opened = diff::new_open_entries(&baseline(previous), current);
+    let closed = diff::new_open_entries(&baseline(current), previous);
+    if opened.is_empty() && closed.is_empty() {
+        println!("No changes since last run");
+        return;
+    }
+    for e in &opened {
+        let svc = e.service.as_deref().unwrap_or("unknown");
+        println!("  + {}:{} ({svc}) now open", e.ip, e.port);
+    }
+    for e in &closed {
+        println!("  - {}:{} no longer open", e.ip, e.port);
+    }
+}
 
-    Ok(())
+fn webhook_config(cli: &Cli) -> Option<WebhookConfig> {
+    cli.webhook.as_ref().map(|url| WebhookConfig {
+        url: url.clone(),
+        timeout: Duration::from_millis(cli.webhook_timeout_ms),
+        format: cli.alert_format,
+    })
 }
 
-fn print_results_table(results: &ScanResults) {
+fn syslog_target(cli: &Cli) -> Option<SyslogTarget> {
+    if let Some(addr) = &cli.syslog {
+        Some(SyslogTarget::Udp(addr.clone()))
+    } else if cli.syslog_local {
+        Some(SyslogTarget::Local)
+    } else {
+        None
+    }
+}
+
+/// Print a progress line with a smoothed ETA to stderr every couple of seconds until `done`.
+async fn report_progress(shared: SharedProgress, done: CancellationToken) {
+    let mut tick = tokio::time::interval(Duration::from_secs(2));
+    tick.tick().await; // first tick fires immediately
+    let mut reported = 0;
+    loop {
+        tokio::select! {
+            _ = done.cancelled() => break,
+            _ = tick.tick() => {}
+        }
+        if verbosity::level() == Verbosity::Verbose {
+            reported = report_new_entries(&shared, reported).await;
+        }
+        let scanned = shared.scanned_done.load(Ordering::Relaxed);
+        let Some(total) = shared.total() else {
+            // Streamed targets (stdin): the total is only known once the stream ends.
+            eprintln!(
+                "  progress: {} scanned of {} so far, open {}",
+                scanned,
+                shared.scanned_total.load(Ordering::Relaxed),
+                shared.open_count.load(Ordering::Relaxed)
+            );
+            continue;
+        };
+        let pct = (scanned * 100).checked_div(total).unwrap_or(0);
+        let eta = shared
+            .eta_seconds(total)
+            .map(progress::format_eta)
+            .unwrap_or_else(|| "--".to_string());
+        eprintln!(
+            "  progress: {}/{} ({}%), open {}, ETA {}",
+            scanned,
+            total,
+            pct,
+            shared.open_count.load(Ordering::Relaxed),
+            eta
+        );
+    }
+}
+
+/// `-v`: list entries found since the last tick, returning how many have been reported.
+async fn report_new_entries(shared: &SharedProgress, reported: usize) -> usize {
+    let entries = shared.entries.lock().await;
+    for e in entries.iter().skip(reported).filter(|e| e.open) {
+        eprintln!(
+            "  found {}:{} {}",
+            e.ip,
+            e.port,
+            e.service.as_deref().unwrap_or("-")
+        );
+    }
+    entries.len()
+}
+
+fn print_plan(plan: &ScanPlan) {
+    println!("\nDry run (no sockets opened):");
+    println!("  connects     : {}", plan.total_connects);
+    println!("  hosts        : {}", plan.host_count);
+    println!("  ports        : {}", plan.port_count);
+    let more = plan.host_count.saturating_sub(plan.sample.len() as u64);
+    if more > 0 {
+        println!(
+            "  sample hosts : {} (+{} more)",
+            plan.sample.join(", "),
+            more
+        );
+    } else {
+        println!("  sample hosts : {}", plan.sample.join(", "));
+    }
+}
+
+/// Build scanner options from the CLI flags; the timeout is passed in since presets may clamp it.
+fn scan_options(
+    cli: &Cli,
+    timeout: Duration,
+    host_names: HashMap<IpAddr, Vec<String>>,
+) -> scanner::ScanOptions {
+    let mut options = scanner::ScanOptions {
+        concurrency: cli.concurrency,
+        timeout,
+        probe_redis: cli.probe_redis,
+        probe_smb: cli.probe_smb,
+        probe_dns_recursion: cli.probe_dns_recursion,
+        probe_kafka: cli.probe_kafka,
+        grab_all: cli.grab_all,
+        note_refused: cli.note_refused,
+        grab_banners: !cli.no_banner,
+        connect_only: cli.connect_only,
+        banner_max_bytes: cli.banner_bytes,
+        probe_budget: cli.probe_budget_ms.map(Duration::from_millis),
+        tls_accept_invalid_certs: true,
+        tls_accept_invalid_hostnames: true,
+        tls_verify: cli.tls_verify,
+        tls_timeout: Duration::from_millis(cli.tls_timeout_ms),
+        tls_client_identity: tls_client_identity(cli),
+        tls_alpn: cli
+            .tls_alpn
+            .iter()
+            .filter(|p| !p.is_empty())
+            .cloned()
+            .collect(),
+        http_user_agent: cli.http_user_agent.clone(),
+        http_host: cli.http_host.clone(),
+        host_names,
+        host_labels: HashMap::new(),
+        advertised: HashMap::new(),
+        http_paths: normalize_http_paths(&cli.http_paths),
+        per_host_concurrency: cli.per_host_concurrency,
+        host_sequential: cli.host_sequential,
+        ramp_up: cli.ramp_up,
+        max_duration: cli.max_duration,
+        source_ports: cli
+            .source_port_range
+            .clone()
+            .map(source_port::SourcePorts::new),
+        proxy: cli
+            .proxy
+            .clone()
+            .map(|proxy| proxy.with_auth(cli.proxy_auth.as_deref())),
+        port_order: cli.order,
+        timestamp_format: cli.timestamp_format,
+        local_time: cli.local_time,
+        entry_tx: None,
+        on_entry: None,
+        // The binary owns the process, so Ctrl-C stops the scan and keeps partial results.
+        install_ctrlc_handler: true,
+        seed: cli.seed,
+    };
+    if let Some(profile) = cli.profile {
+        options.enable_probes(ports::profile_preset(profile).1);
+    }
+    options
+}
+
+/// The --tls-client-cert/--tls-client-key identity. Loading problems only warn: the scan still
+/// runs, and mutual-TLS services just fail the handshake as they would without it.
+fn tls_client_identity(cli: &Cli) -> Option<scanner::TlsIdentity> {
+    match (&cli.tls_client_cert, &cli.tls_client_key) {
+        (Some(cert), Some(key)) => match scanner::TlsIdentity::from_pem_files(cert, key) {
+            Ok(identity) => Some(identity),
+            Err(e) => {
+                eprintln!("Warning: TLS client identity not loaded, scanning without it: {e:#}");
+                None
+            }
+        },
+        (None, None) => None,
+        _ => {
+            eprintln!("Warning: --tls-client-cert and --tls-client-key must be given together");
+            None
+        }
+    }
+}
+
+/// Trim, drop blanks, and make each path absolute; falls back to `/` when nothing remains.
+fn normalize_http_paths(raw: &[String]) -> Vec<String> {
+    let mut paths: Vec<String> = raw
+        .iter()
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            if p.starts_with('/') {
+                p.to_string()
+            } else {
+                format!("/{p}")
+            }
+        })
+        .collect();
+    if paths.is_empty() {
+        paths.push("/".to_string());
+    }
+    if paths.len() > scanner::MAX_HTTP_PATHS {
+        eprintln!(
+            "Warning: only the first {} HTTP paths are probed",
+            scanner::MAX_HTTP_PATHS
+        );
+    }
+    paths
+}
+
+/// The table's address column: the IP, plus the vhost name it was probed as.
+fn entry_addr(e: &ScanEntry) -> String {
+    match &e.host {
+        Some(host) => format!("{} ({host})", e.ip),
+        None => e.ip.clone(),
+    }
+}
+
+/// How the results table is filtered and laid out.
+#[derive(Debug, Clone, Copy)]
+struct TableView {
+    min_severity: Option<Severity>,
+    /// One row per host listing its open ports instead of one row per port.
+    group_by_host: bool,
+    palette: Palette,
+}
+
+fn print_results_table(results: &ScanResults, view: TableView) {
+    let palette = view.palette;
+    let shown: Vec<&ScanEntry> = results
+        .entries
+        .iter()
+        .filter(|e| e.open && view.min_severity.is_none_or(|min| rating(e) >= min))
+        .collect();
+    println!(
+        "\nOpen ports: {} (scanned: {}, closed: {}, filtered: {})",
+        results.open_count, results.scanned_done, results.closed_count, results.filtered_count
+    );
+    let refused: Vec<&ScanEntry> = results.entries.iter().filter(|e| !e.open).collect();
+    if !refused.is_empty() {
+        let hosts: HashSet<&str> = refused.iter().map(|e| e.ip.as_str()).collect();
+        println!(
+            "Refused (closed, host up): {} port(s) on {} host(s), listed in the JSON output",
+            refused.len(),
+            hosts.len()
+        );
+    }
+    if let Some(rate) = results.open_rate() {
+        println!(
+            "Hosts up: {} (open rate: {:.1}%)",
+            results.hosts_up(),
+            rate * 100.0
+        );
+    }
+    if !results.live_hosts.is_empty() {
+        println!(
+            "Live hosts: {} (answered on any port; {} without open ports)",
+            results.live_hosts.len(),
+            results.live_without_open()
+        );
+    }
+    if let Some(min) = view.min_severity {
+        println!("Showing {} at or above severity {min}", shown.len());
+    }
+    print_baseline_summary(results);
+    if view.group_by_host {
+        print_host_rows(&shown, palette);
+        return;
+    }
     let mut ip_w = 2usize.max("ip".len());
     let mut banner_w = 6usize.max("banner".len());
-    for e in &results.entries {
-        ip_w = ip_w.max(e.ip.len());
+    for e in &shown {
+        ip_w = ip_w.max(entry_addr(e).len());
         if let Some(b) = &e.banner {
             banner_w = banner_w.max(b.len().min(60));
         }
     }
     let port_w = 4usize.max("port".len());
+    let risk_w = "critical".len();
     let lat_w = 9usize.max("latency_ms".len());
     let mut svc_w = 7usize.max("service".len());
-    for e in &results.entries {
+    for e in &shown {
         if let Some(s) = &e.service {
             svc_w = svc_w.max(s.len());
         }
     }
 
+    // Only show the label column when the targets file supplied labels, and the baseline
+    // column when --baseline was given.
+    let show_label = shown.iter().any(|e| e.label.is_some());
+    let label_hdr = if show_label { "  label" } else { "" };
+    let show_baseline = shown.iter().any(|e| e.baseline.is_some());
+    let base_w = "unchanged".len();
+    let baseline_hdr = if show_baseline {
+        format!("{:<base_w$}  ", "baseline")
+    } else {
+        String::new()
+    };
+
     println!(
-        "\nOpen ports: {} (scanned: {})",
-        results.open_count, results.scanned_done
-    );
-    println!(
-        "{:<ip_w$}  {:>port_w$}  {:<svc_w$}  {:>lat_w$}  {:<banner_w$}",
+        "{}{:<ip_w$}  {:>port_w$}  {:<svc_w$}  {:<risk_w$}  {:>lat_w$}  {:<banner_w$}{}",
+        baseline_hdr,
         "ip",
         "port",
         "service",
+        "risk",
         "latency_ms",
         "banner",
+        label_hdr,
         ip_w = ip_w,
         port_w = port_w,
         svc_w = svc_w,
+        risk_w = risk_w,
         lat_w = lat_w,
         banner_w = banner_w
     );
     println!(
-        "{:-<ip_w$}  {:-<port_w$}  {:-<svc_w$}  {:-<lat_w$}  {:-<banner_w$}",
+        "{}{:-<ip_w$}  {:-<port_w$}  {:-<svc_w$}  {:-<risk_w$}  {:-<lat_w$}  {:-<banner_w$}{}",
+        if show_baseline {
+            format!("{:-<base_w$}  ", "")
+        } else {
+            String::new()
+        },
         "",
         "",
         "",
         "",
         "",
+        "",
+        if show_label { "  -----" } else { "" },
         ip_w = ip_w,
         port_w = port_w,
         svc_w = svc_w,
+        risk_w = risk_w,
         lat_w = lat_w,
         banner_w = banner_w
     );
-    for e in &results.entries {
+    for e in shown {
         let mut bsnip = e.banner.clone().unwrap_or_default();
         if bsnip.len() > 60 {
             bsnip.truncate(60);
         }
-        println!(
-            "{:<ip_w$}  {:>port_w$}  {:<svc_w$}  {:>lat_w$}  {:<banner_w$}",
-            e.ip,
+        let label = match (&e.label, show_label) {
+            (Some(l), true) => format!("  {l}"),
+            _ => String::new(),
+        };
+        let status = match (e.baseline, show_baseline) {
+            (Some(status), true) => format!("{:<base_w$}  ", status.as_str()),
+            (None, true) => format!("{:<base_w$}  ", ""),
+            _ => String::new(),
+        };
+        let line = format!(
+            "{}{:<ip_w$}  {:>port_w$}  {:<svc_w$}  {:<risk_w$}  {:>lat_w$}  {:<banner_w$}{}",
+            status,
+            entry_addr(e),
             e.port,
             e.service.clone().unwrap_or_default(),
+            e.severity.map(Severity::as_str).unwrap_or_default(),
             e.latency_ms,
             bsnip,
+            label,
             ip_w = ip_w,
             port_w = port_w,
             svc_w = svc_w,
+            risk_w = risk_w,
             lat_w = lat_w,
             banner_w = banner_w
         );
+        println!("{}", palette.entry_row(e, &line));
     }
 }
 
-fn write_results_json(path: &std::path::Path, results: &ScanResults) -> anyhow::Result<()> {
-    let file = File::create(path)?;
-    serde_json::to_writer_pretty(file, results)?;
-    Ok(())
+/// With --baseline: how many open ports are new or unchanged, and which baseline ports are
+/// no longer open.
+fn print_baseline_summary(results: &ScanResults) {
+    let annotated: Vec<BaselineStatus> =
+        results.entries.iter().filter_map(|e| e.baseline).collect();
+    if annotated.is_empty() && results.baseline_missing.is_empty() {
+        return;
+    }
+    let new = annotated
+        .iter()
+        .filter(|&&s| s == BaselineStatus::New)
+        .count();
+    println!(
+        "Baseline: {new} new, {} unchanged, {} missing",
+        annotated.len() - new,
+        results.baseline_missing.len()
+    );
+    for p in &results.baseline_missing {
+        let svc = p.service.as_deref().unwrap_or("unknown");
+        println!(
+            "  - {}:{} ({svc}) missing: open in the baseline, not now",
+            p.ip, p.port
+        );
+    }
 }
 
-fn parse_targets_arg(input: Option<&str>) -> anyhow::Result<Vec<IpAddr>> {
-    let mut out: Vec<IpAddr> = Vec::new();
-    if let Some(s) = input {
-        let s_trim = s.trim();
-        if !s_trim.is_empty() {
-            let p = Path::new(s_trim);
-            if p.exists() && p.is_file() {
-                let content = fs::read_to_string(p)?;
-                for line in content.lines() {
-                    let ln = line.split('#').next().unwrap_or("").trim();
-                    if ln.is_empty() {
-                        continue;
-                    }
-                    for tok in ln.split(|c: char| c.is_whitespace() || c == ',') {
-                        let tok = tok.trim();
-                        if tok.is_empty() {
-                            continue;
-                        }
-                        push_target_token(tok, &mut out)?;
-                    }
-                }
-            } else {
-                // Single token (IP or CIDR), or comma-separated list
-                for tok in s_trim.split(|c: char| c.is_whitespace() || c == ',') {
-                    let tok = tok.trim();
-                    if tok.is_empty() {
-                        continue;
-                    }
-                    push_target_token(tok, &mut out)?;
+/// Entries from older result files carry no rating; treat them as informational.
+fn rating(e: &ScanEntry) -> Severity {
+    e.severity.unwrap_or(Severity::Info)
+}
+
+/// `--group-by-host`: `192.168.1.10   22(ssh), 80(http), 443(https)`, hosts in address order,
+/// each row colored by its most severe finding.
+fn print_host_rows(shown: &[&ScanEntry], palette: Palette) {
+    let mut hosts: BTreeMap<(Option<IpAddr>, String), Vec<&ScanEntry>> = BTreeMap::new();
+    for &e in shown {
+        hosts
+            .entry((e.ip.parse().ok(), entry_addr(e)))
+            .or_default()
+            .push(e);
+    }
+    let host_w = hosts
+        .keys()
+        .map(|(_, addr)| addr.len())
+        .max()
+        .unwrap_or(0)
+        .max("host".len());
+    println!("{:<host_w$}  open ports", "host");
+    println!("{:-<host_w$}  {:-<10}", "", "");
+    for ((_, addr), mut entries) in hosts {
+        entries.sort_by_key(|e| e.port);
+        let ports: Vec<String> = entries
+            .iter()
+            .map(|e| {
+                let port = match &e.service {
+                    Some(svc) => format!("{}({svc})", e.port),
+                    None => e.port.to_string(),
+                };
+                match e.baseline {
+                    Some(BaselineStatus::New) => format!("{port}[new]"),
+                    _ => port,
                 }
-            }
-        }
+            })
+            .collect();
+        let line = format!("{addr:<host_w$}  {}", ports.join(", "));
+        let worst = entries
+            .iter()
+            .max_by_key(|e| rating(e))
+            .expect("hosts have at least one entry");
+        println!("{}", palette.entry_row(worst, &line));
     }
-    Ok(out)
 }
 
-fn push_target_token(tok: &str, out: &mut Vec<IpAddr>) -> anyhow::Result<()> {
-    if tok.contains('/') {
-        let net: IpNet = tok.parse()?;
-        out.extend(netdetect::expand_cidr_to_ips(net));
-    } else {
-        let ip: IpAddr = tok.parse()?;
-        out.push(ip);
+/// Call out SMB servers that don't require signing: their sessions can be relayed (NTLM relay).
+fn print_smb_relay_hosts(results: &ScanResults, palette: Palette) {
+    let hosts: Vec<String> = results
+        .entries
+        .iter()
+        .filter(|e| e.banner.as_deref().is_some_and(smb::banner_has_relay_risk))
+        .map(|e| format!("{}:{}", e.ip, e.port))
+        .collect();
+    if !hosts.is_empty() {
+        let line = format!(
+            "SMB signing not required (NTLM relay risk): {}",
+            hosts.join(", ")
+        );
+        println!("\n{}", palette.alert(&line));
+    }
+}
+
+/// Call out services that handed out data without asking for credentials (`auth=disabled`).
+fn print_unauthenticated_services(results: &ScanResults, palette: Palette) {
+    let exposed: Vec<String> = results
+        .entries
+        .iter()
+        .filter_map(|e| {
+            let banner = e.banner.as_deref()?;
+            let words: Vec<&str> = banner.split(' ').collect();
+            words
+                .contains(&"auth=disabled")
+                .then(|| format!("{}:{} ({})", e.ip, e.port, words[0]))
+        })
+        .collect();
+    if !exposed.is_empty() {
+        let line = format!(
+            "Reachable WITHOUT authentication (data exposure): {}",
+            exposed.join(", ")
+        );
+        println!("\n{}", palette.alert(&line));
+    }
+    if exposed.iter().any(|e| e.ends_with("(docker)")) {
+        println!(
+            "{}",
+            palette.alert(
+                "  HIGH: an unauthenticated Docker API grants remote code execution on its host"
+            )
+        );
+    }
+}
+
+/// One-line throughput footer. Connect and probe times are summed over tasks, so they can add up
+/// to more than the scan took.
+fn print_stats_footer(results: &ScanResults) {
+    let Some(timing) = results.timing else {
+        return;
+    };
+    let secs = |ms: u64| ms as f64 / 1000.0;
+    let rate = timing
+        .connects_per_sec()
+        .map(|r| format!("{r:.0}/s"))
+        .unwrap_or_else(|| "-".to_string());
+    let latency = results
+        .avg_open_latency_ms()
+        .map(|l| format!("{l:.1} ms"))
+        .unwrap_or_else(|| "-".to_string());
+    println!(
+        "\nStats: {} connects in {:.1} s ({rate}), avg open latency {latency}; time in connects {:.1} s, in probes {:.1} s (summed over tasks)",
+        timing.connects,
+        secs(timing.duration_ms),
+        secs(timing.connect_ms),
+        secs(timing.probe_ms)
+    );
+}
+
+/// `--output -` writes the JSON to stdout instead of a file.
+fn output_is_stdout(output: Option<&Path>) -> bool {
+    output == Some(Path::new("-"))
+}
+
+/// Which service probes will run, for `-v`.
+fn active_probes(options: &scanner::ScanOptions) -> Vec<&'static str> {
+    if !options.grab_banners || options.connect_only {
+        return Vec::new();
+    }
+    let mut probes = scanner::BUILTIN_PROBES.to_vec();
+    let optional = [
+        (options.probe_redis, "redis ping"),
+        (options.probe_smb, "smb negotiate"),
+        (options.probe_dns_recursion, "dns recursion"),
+        (options.probe_kafka, "kafka"),
+        (options.grab_all, "newline nudge"),
+    ];
+    probes.extend(optional.iter().filter(|(on, _)| *on).map(|(_, name)| *name));
+    probes
+}
+
+/// Size guard for CIDR expansion: `--min-prefix-len`, or none at all with `--force`.
+fn min_prefix_len(cli: &Cli) -> Option<u8> {
+    (!cli.force).then_some(cli.min_prefix_len)
+}
+
+/// Resolver for hostname targets, bounded by --dns-concurrency and --dns-timeout-ms.
+fn resolver(cli: &Cli) -> resolve::Resolver {
+    resolve::Resolver::new(
+        cli.dns_concurrency,
+        Duration::from_millis(cli.dns_timeout_ms),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scheduled_output_names_carry_timestamp() {
+        let at = OffsetDateTime::from_unix_timestamp(1_767_323_045).unwrap(); // 2026-01-02 03:04:05Z
+        assert_eq!(
+            timestamped_path(Path::new("out/scan.json"), at),
+            PathBuf::from("out/scan-20260102T030405Z.json")
+        );
+    }
+
+    #[test]
+    fn ports_file_missing_is_an_error_and_empty_falls_back() {
+        let err =
+            load_ports_file(Path::new("no-such-ports.txt"), PortsFallback::Default).unwrap_err();
+        assert!(err.to_string().contains("ports file not found"), "{err}");
+        let empty = std::env::temp_dir().join(format!("lan-scan-ports-{}.txt", std::process::id()));
+        std::fs::write(&empty, "# nothing yet\n").unwrap();
+        let list = load_ports_file(&empty, PortsFallback::Default);
+        let quick = load_ports_file(&empty, PortsFallback::Quick);
+        let none = load_ports_file(&empty, PortsFallback::None);
+        let _ = std::fs::remove_file(&empty);
+        assert_eq!(list.unwrap(), ports::default_ports());
+        assert_eq!(quick.unwrap(), ports::quick_ports());
+        assert!(none.unwrap_err().to_string().contains("lists no ports"));
+    }
+
+    #[test]
+    fn discovery_only_annotates_hosts_in_the_target_set() {
+        let (a, b): (IpAddr, IpAddr) = ("10.0.0.5".parse().unwrap(), "10.0.0.6".parse().unwrap());
+        let mut job = ScanJob {
+            targets: [a, b].into_iter().collect(),
+            ports: vec![22],
+            options: scanner::ScanOptions::default(),
+            from_stdin: false,
+            pairs: Vec::new(),
+        };
+        let found = [
+            (a, 8009, "googlecast".to_string()),
+            ("192.168.7.9".parse().unwrap(), 631, "ipp".to_string()),
+        ];
+        add_discovered(&mut job, found.into_iter());
+        assert_eq!(job.targets.hosts().collect::<Vec<_>>(), [a, b]);
+        assert_eq!(job.ports, vec![22]);
+        assert_eq!(job.options.advertised.len(), 1);
+        assert!(job.options.advertised.contains_key(&(a, 8009)));
+        assert_eq!(job.options.advertised_extra_sockets(&job.ports), 1);
     }
-    Ok(())
 }
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use crate::error::{Result, ScanError};
 use if_addrs::{get_if_addrs, IfAddr};
 use ipnet::{IpNet, Ipv4Net};
 use std::collections::HashSet;
@@ -34,15 +34,278 @@ pub fn detect_local_cidrs() -> Result<Vec<IpNet>> {
 /// For IPv4, excludes the network and broadcast addresses.
 /// IPv6 is not scanned in this project and returns an empty list.
 pub fn expand_cidr_to_ips(cidr: IpNet) -> Vec<IpAddr> {
-    match cidr {
-        IpNet::V4(n4) => expand_ipv4net_hosts(n4)
-            .into_iter()
-            .map(IpAddr::V4)
-            .collect(),
-        IpNet::V6(_) => Vec::new(),
+    iter_cidr_hosts(cidr).collect()
+}
+
+/// Lazily yield the host addresses of a CIDR, in ascending order, with the same rules as
+/// `expand_cidr_to_ips` but without materializing the list. The iterator knows its exact length.
+pub fn iter_cidr_hosts(cidr: IpNet) -> impl ExactSizeIterator<Item = IpAddr> + Clone + Send {
+    let hosts = match cidr {
+        IpNet::V4(n4) => {
+            // Numeric range between the network and broadcast addresses (both excluded).
+            let start = u32::from(n4.network());
+            let end = u32::from(n4.broadcast());
+            if end <= start + 1 {
+                // Too small to have host addresses
+                0..0
+            } else {
+                start + 1..end
+            }
+        }
+        IpNet::V6(_) => 0..0,
+    };
+    hosts.map(|n| IpAddr::V4(Ipv4Addr::from(n)))
+}
+
+/// Networks with a shorter prefix than this (i.e. larger than a /16) are refused unless forced.
+pub const DEFAULT_MIN_PREFIX_LEN: u8 = 16;
+
+/// Like `expand_cidr_to_ips`, but refuses IPv4 networks whose prefix is shorter than
+/// `min_prefix_len` before allocating anything. `None` disables the guard (`--force`).
+pub fn expand_cidr_checked(cidr: IpNet, min_prefix_len: Option<u8>) -> Result<Vec<IpAddr>> {
+    check_cidr_size(cidr, min_prefix_len)?;
+    Ok(expand_cidr_to_ips(cidr))
+}
+
+/// The size guard behind `expand_cidr_checked`, without expanding anything.
+fn check_cidr_size(cidr: IpNet, min_prefix_len: Option<u8>) -> Result<()> {
+    if let (IpNet::V4(n4), Some(min)) = (cidr, min_prefix_len) {
+        if n4.prefix_len() < min {
+            let hosts = 1u64 << (32 - n4.prefix_len());
+            return Err(ScanError::InvalidTarget {
+                spec: cidr.to_string(),
+                reason: format!(
+                    "refusing to expand {cidr} ({} hosts); use a smaller prefix or --force",
+                    human_count(hosts)
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// `16777216` -> `16M`, `65536` -> `65K`.
+fn human_count(n: u64) -> String {
+    match n {
+        n if n >= 1 << 20 => format!("{}M", n / 1_000_000),
+        n if n >= 1 << 10 => format!("{}K", n / 1_000),
+        n => n.to_string(),
+    }
+}
+
+/// Expand one target spec — a CIDR (`10.0.0.0/24`) or a single IP — into host addresses,
+/// subject to the `min_prefix_len` size guard.
+pub fn expand_target(spec: &str, min_prefix_len: Option<u8>) -> Result<Vec<IpAddr>> {
+    let mut set = TargetSet::default();
+    set.push_spec(spec, min_prefix_len)?;
+    Ok(set.hosts().collect())
+}
+
+/// Expand CIDR/IP specs into one flat list in which overlapping specs yield each address once,
+/// in first-seen order. `NoTargets` if that list is empty.
+pub fn expand_targets<S: AsRef<str>>(
+    specs: &[S],
+    min_prefix_len: Option<u8>,
+) -> Result<Vec<IpAddr>> {
+    Ok(target_set(specs, min_prefix_len)?.hosts().collect())
+}
+
+/// Like `expand_targets`, but keeps networks unexpanded: the addresses are only produced as
+/// `TargetSet::hosts` is iterated.
+pub fn target_set<S: AsRef<str>>(specs: &[S], min_prefix_len: Option<u8>) -> Result<TargetSet> {
+    let mut set = TargetSet::default();
+    for spec in specs {
+        set.push_spec(spec.as_ref(), min_prefix_len)?;
+    }
+    if set.is_empty() {
+        return Err(ScanError::NoTargets);
+    }
+    Ok(set)
+}
+
+/// Scan targets as they were given, single hosts and whole networks, in first-seen order.
+///
+/// Networks are not expanded up front: `hosts` yields their addresses one at a time, so a /16
+/// costs no more memory than a single IP. Overlaps are settled as specs are added, and every
+/// address is yielded once.
+#[derive(Debug, Clone, Default)]
+pub struct TargetSet {
+    items: Vec<Target>,
+    /// Single hosts among `items`; none of them lies inside a network listed before it.
+    hosts: HashSet<IpAddr>,
+    len: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Target {
+    Host(IpAddr),
+    /// A network and how many of its addresses no earlier target covers.
+    Net(IpNet, usize),
+}
+
+impl TargetSet {
+    /// Add one target spec, a CIDR (`10.0.0.0/24`) or a single IP, subject to the
+    /// `min_prefix_len` size guard.
+    pub fn push_spec(&mut self, spec: &str, min_prefix_len: Option<u8>) -> Result<()> {
+        let spec = spec.trim();
+        if spec.contains('/') {
+            let net: IpNet = spec.parse().map_err(|e| ScanError::InvalidTarget {
+                spec: spec.to_string(),
+                reason: format!("invalid CIDR {spec}: {e}"),
+            })?;
+            check_cidr_size(net, min_prefix_len)?;
+            self.push_net(net);
+        } else {
+            let ip: IpAddr = spec.parse().map_err(|e| ScanError::InvalidTarget {
+                spec: spec.to_string(),
+                reason: format!("invalid IP {spec}: {e}"),
+            })?;
+            self.push_host(ip);
+        }
+        Ok(())
+    }
+
+    /// Add a single address; a no-op if it is already a target.
+    pub fn push_host(&mut self, ip: IpAddr) {
+        if !self.contains(ip) {
+            self.hosts.insert(ip);
+            self.items.push(Target::Host(ip));
+            self.len += 1;
+        }
+    }
+
+    /// Add the host addresses of a network (see `iter_cidr_hosts`); those already targeted keep
+    /// their earlier place.
+    pub fn push_net(&mut self, net: IpNet) {
+        let mut fresh = iter_cidr_hosts(net).len();
+        for item in &self.items {
+            match *item {
+                Target::Net(earlier, _) if earlier.contains(&net) => return,
+                // Networks nest or don't overlap at all, so this subtracts each address once.
+                Target::Net(earlier, n) if net.contains(&earlier) => fresh -= n,
+                Target::Host(ip) if cidr_has_host(net, ip) => fresh -= 1,
+                _ => {}
+            }
+        }
+        self.items.push(Target::Net(net, fresh));
+        self.len += fresh;
+    }
+
+    /// Number of distinct addresses `hosts` yields.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether `ip` is one of the targets.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.hosts.contains(&ip)
+            || self
+                .items
+                .iter()
+                .any(|item| matches!(*item, Target::Net(net, _) if cidr_has_host(net, ip)))
+    }
+
+    /// Every target address once, in first-seen order, expanded lazily.
+    pub fn hosts(&self) -> TargetHosts<'_> {
+        TargetHosts {
+            set: self,
+            next_item: 0,
+            current: None,
+            remaining: self.len,
+        }
+    }
+}
+
+impl FromIterator<IpAddr> for TargetSet {
+    fn from_iter<I: IntoIterator<Item = IpAddr>>(iter: I) -> Self {
+        let mut set = Self::default();
+        for ip in iter {
+            set.push_host(ip);
+        }
+        set
     }
 }
 
+/// Iterator returned by `TargetSet::hosts`.
+#[derive(Debug, Clone)]
+pub struct TargetHosts<'a> {
+    set: &'a TargetSet,
+    next_item: usize,
+    /// The network being walked and the smaller networks listed before it, whose addresses it
+    /// skips.
+    current: Option<(std::ops::Range<u32>, Vec<IpNet>)>,
+    remaining: usize,
+}
+
+impl Iterator for TargetHosts<'_> {
+    type Item = IpAddr;
+
+    fn next(&mut self) -> Option<IpAddr> {
+        loop {
+            if let Some((range, inner)) = &mut self.current {
+                for n in range.by_ref() {
+                    let ip = IpAddr::V4(Ipv4Addr::from(n));
+                    if !self.set.hosts.contains(&ip)
+                        && !inner.iter().any(|net| cidr_has_host(*net, ip))
+                    {
+                        self.remaining -= 1;
+                        return Some(ip);
+                    }
+                }
+                self.current = None;
+            }
+            match *self.set.items.get(self.next_item)? {
+                Target::Host(ip) => {
+                    self.next_item += 1;
+                    self.remaining -= 1;
+                    return Some(ip);
+                }
+                Target::Net(net, fresh) => {
+                    self.next_item += 1;
+                    if let (IpNet::V4(n4), true) = (net, fresh > 0) {
+                        let inner = self.set.items[..self.next_item - 1]
+                            .iter()
+                            .filter_map(|item| match *item {
+                                Target::Net(earlier, _) if net.contains(&earlier) => Some(earlier),
+                                _ => None,
+                            })
+                            .collect();
+                        let (start, end) = (u32::from(n4.network()), u32::from(n4.broadcast()));
+                        self.current = Some((start + 1..end, inner));
+                    }
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for TargetHosts<'_> {}
+
+/// Whether `ip` is one of the addresses `iter_cidr_hosts(net)` yields.
+fn cidr_has_host(net: IpNet, ip: IpAddr) -> bool {
+    match (net, ip) {
+        (IpNet::V4(n4), IpAddr::V4(v4)) => {
+            let n = u32::from(v4);
+            n > u32::from(n4.network()) && n < u32::from(n4.broadcast())
+        }
+        _ => false,
+    }
+}
+
+/// Drop repeated addresses, keeping the first occurrence of each.
+pub fn dedup_ips(ips: &mut Vec<IpAddr>) {
+    let mut seen = HashSet::with_capacity(ips.len());
+    ips.retain(|ip| seen.insert(*ip));
+}
+
 /// Helper: convert an IPv4 address into its default /24 network.
 pub fn ipv4_to_default_cidr(ip: Ipv4Addr) -> Ipv4Net {
     let o = ip.octets();
@@ -50,17 +313,6 @@ pub fn ipv4_to_default_cidr(ip: Ipv4Addr) -> Ipv4Net {
     Ipv4Net::new(net, 24).expect("/24 is always valid")
 }
 
-fn expand_ipv4net_hosts(net: Ipv4Net) -> Vec<Ipv4Addr> {
-    // Use inclusive range of numeric IPs, then skip network and broadcast.
-    let start = u32::from(net.network());
-    let end = u32::from(net.broadcast());
-    if end <= start + 1 {
-        // Too small to have host addresses
-        return Vec::new();
-    }
-    (start + 1..end).map(Ipv4Addr::from).collect()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -88,4 +340,70 @@ mod tests {
             vec![Ipv4Addr::new(192, 168, 1, 1), Ipv4Addr::new(192, 168, 1, 2),]
         );
     }
+
+    #[test]
+    fn cidr_iterator_is_lazy_and_exact() {
+        let mut hosts = iter_cidr_hosts("10.0.0.0/8".parse().unwrap());
+        assert_eq!(hosts.len(), (1 << 24) - 2);
+        assert_eq!(hosts.next(), Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))));
+        assert_eq!(iter_cidr_hosts("10.0.0.7/32".parse().unwrap()).len(), 0);
+    }
+
+    #[test]
+    fn target_set_expands_lazily_and_counts_overlaps_once() {
+        let ip = |s: &str| -> IpAddr { s.parse().unwrap() };
+        let mut set = TargetSet::default();
+        for spec in [
+            "10.0.0.9",
+            "10.0.0.8/30",
+            "10.0.0.0/8",
+            "10.0.0.0/29",
+            "10.0.0.9",
+        ] {
+            set.push_spec(spec, None).unwrap();
+        }
+        assert_eq!(set.len(), (1 << 24) - 2);
+        assert_eq!(set.hosts().len(), set.len());
+        let first: Vec<IpAddr> = set.hosts().take(5).collect();
+        assert_eq!(
+            first,
+            [
+                ip("10.0.0.9"),
+                ip("10.0.0.10"),
+                ip("10.0.0.1"),
+                ip("10.0.0.2"),
+                ip("10.0.0.3")
+            ]
+        );
+        assert!(set.contains(ip("10.200.0.1")) && !set.contains(ip("10.255.255.255")));
+
+        let mut small = TargetSet::default();
+        for spec in ["10.1.0.5", "10.1.0.0/28", "10.1.0.4/30", "10.1.0.0/24"] {
+            small.push_spec(spec, None).unwrap();
+        }
+        let hosts: Vec<IpAddr> = small.hosts().collect();
+        assert_eq!(hosts.len(), small.len());
+        assert_eq!(hosts.len(), 254);
+        assert_eq!(hosts.iter().collect::<HashSet<_>>().len(), 254);
+        assert_eq!(
+            &hosts[..3],
+            [ip("10.1.0.5"), ip("10.1.0.1"), ip("10.1.0.2")]
+        );
+        assert_eq!(hosts[14], ip("10.1.0.15"));
+    }
+
+    #[test]
+    fn size_guard_refuses_below_min_prefix() {
+        let min = Some(DEFAULT_MIN_PREFIX_LEN);
+        let at_limit: IpNet = "10.0.0.0/16".parse().unwrap();
+        assert_eq!(expand_cidr_checked(at_limit, min).unwrap().len(), 65_534);
+        let too_big: IpNet = "10.0.0.0/15".parse().unwrap();
+        assert!(expand_cidr_checked(too_big, min).is_err());
+        let err = expand_cidr_checked("10.0.0.0/8".parse().unwrap(), min).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "refusing to expand 10.0.0.0/8 (16M hosts); use a smaller prefix or --force"
+        );
+        assert_eq!(expand_cidr_checked(too_big, None).unwrap().len(), 131_070);
+    }
 }
@@ -1,52 +1,149 @@
+use crate::rng::ScanRng;
+use crate::scanner::ProbeSet;
 use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
+use std::str::FromStr;
 
 /// Parse a ports file content into a deduplicated list of TCP ports (1..=65535).
 ///
 /// Supported formats per line:
-/// - single port number: `80`
+/// - single port number: `80`, or hex: `0x1F90`
 /// - inclusive range: `8000-8010`
+/// - open-ended range: `60000-` (through 65535) or `-1024` (from 1)
 /// - comments: everything after `#` is ignored
 /// - whitespace and blank lines are ignored
 pub fn parse_ports_str(s: &str) -> Result<Vec<u16>> {
     let mut out: Vec<u16> = Vec::new();
     let mut seen = std::collections::HashSet::new();
 
-    for (idx, raw_line) in s.lines().enumerate() {
-        let line_no = idx + 1;
-        // Strip comments and trim
-        let line = raw_line.split('#').next().map(str::trim).unwrap_or("");
-        if line.is_empty() {
+    for (line_no, parsed) in parse_lines(s) {
+        let Some((start, end)) = parsed.with_context(|| format!("line {line_no}"))? else {
             continue;
+        };
+        for p in start..=end {
+            if seen.insert(p) {
+                out.push(p);
+            }
         }
+    }
 
-        // Range `start-end`
-        if let Some((a, b)) = line.split_once('-') {
-            let start = parse_port_str(a.trim())
-                .with_context(|| format!("line {line_no}: invalid start in range: {a}"))?;
-            let end = parse_port_str(b.trim())
-                .with_context(|| format!("line {line_no}: invalid end in range: {b}"))?;
-            if start > end {
-                bail!("line {line_no}: invalid range {start}-{end} (start > end)");
-            }
-            for p in start..=end {
-                if seen.insert(p) {
-                    out.push(p);
+    Ok(out)
+}
+
+/// One bad line of a ports list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[error("line {line}: {message}")]
+pub struct PortParseError {
+    /// 1-based line number.
+    pub line: usize,
+    /// What's wrong with it, including the underlying cause.
+    pub message: String,
+}
+
+impl PortParseError {
+    fn new(line: usize, error: &anyhow::Error) -> Self {
+        Self {
+            line,
+            message: format!("{error:#}"),
+        }
+    }
+}
+
+/// `parse_ports_str` without the fail-fast: parsing continues past bad lines and every problem
+/// is returned together, so a long file can be fixed in one pass.
+pub fn parse_ports_str_all(s: &str) -> std::result::Result<Vec<u16>, Vec<PortParseError>> {
+    let report = check_ports_str(s);
+    if report.errors.is_empty() {
+        Ok(report.ports)
+    } else {
+        Err(report.errors)
+    }
+}
+
+/// What a ports file holds, gathered without stopping at the first bad line (`--check-ports`).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PortsReport {
+    /// Distinct ports, in order of first appearance.
+    pub ports: Vec<u16>,
+    /// Repeats that were collapsed (a port listed three times counts two).
+    pub duplicates: usize,
+    /// Every line that didn't parse.
+    pub errors: Vec<PortParseError>,
+}
+
+/// Like `parse_ports_str`, but keeps going past bad lines and counts duplicates.
+pub fn check_ports_str(s: &str) -> PortsReport {
+    let mut report = PortsReport::default();
+    let mut seen = std::collections::HashSet::new();
+    for (line_no, parsed) in parse_lines(s) {
+        match parsed {
+            Ok(Some((start, end))) => {
+                for p in start..=end {
+                    if seen.insert(p) {
+                        report.ports.push(p);
+                    } else {
+                        report.duplicates += 1;
+                    }
                 }
             }
-            continue;
+            Ok(None) => {}
+            Err(e) => report.errors.push(PortParseError::new(line_no, &e)),
         }
+    }
+    report
+}
 
-        // Single number
-        let p = parse_port_str(line)
-            .with_context(|| format!("line {line_no}: invalid port value: {line}"))?;
-        if seen.insert(p) {
-            out.push(p);
+/// Each line with its 1-based number: `None` for blank and comment-only lines, otherwise the
+/// inclusive range it names (a single port is a range of one).
+fn parse_lines(s: &str) -> impl Iterator<Item = (usize, Result<Option<(u16, u16)>>)> + '_ {
+    s.lines()
+        .enumerate()
+        .map(|(idx, raw_line)| (idx + 1, parse_line(raw_line)))
+}
+
+fn parse_line(raw_line: &str) -> Result<Option<(u16, u16)>> {
+    // Strip comments and trim
+    let line = raw_line.split('#').next().map(str::trim).unwrap_or("");
+    if line.is_empty() {
+        return Ok(None);
+    }
+
+    // Range `start-end`; a missing bound runs to the end of the port space.
+    if let Some((a, b)) = line.split_once('-') {
+        let (a, b) = (a.trim(), b.trim());
+        if a.is_empty() && b.is_empty() {
+            bail!("invalid range: {line} (needs at least one bound)");
+        }
+        let start = match a {
+            "" => 1,
+            a => parse_port_str(a).with_context(|| format!("invalid start in range: {a}"))?,
+        };
+        let end = match b {
+            "" => u16::MAX,
+            b => parse_port_str(b).with_context(|| format!("invalid end in range: {b}"))?,
+        };
+        if start > end {
+            bail!("invalid range {start}-{end} (start > end)");
         }
+        return Ok(Some((start, end)));
     }
 
-    Ok(out)
+    // Single number
+    let p = parse_port_str(line).with_context(|| format!("invalid port value: {line}"))?;
+    Ok(Some((p, p)))
+}
+
+/// Parse an inline ports list such as `22,80,443,8000-8010` (commas, whitespace, or newlines).
+///
+/// Uses the same per-token rules as `parse_ports_str`, so ranges and `#` comments work too.
+pub fn parse_ports_list(s: &str) -> Result<Vec<u16>> {
+    let lines: Vec<&str> = s
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|t| !t.is_empty())
+        .collect();
+    parse_ports_str(&lines.join("\n"))
 }
 
 /// Load a ports list from a file path. Errors if the file cannot be read or parsed.
@@ -56,14 +153,64 @@ pub fn load_ports_from_path(path: impl AsRef<Path>) -> Result<Vec<u16>> {
     parse_ports_str(&content)
 }
 
-/// Load a ports list from a file, or return a safe default list if missing or empty.
-pub fn load_ports_or_default(path: impl AsRef<Path>) -> Vec<u16> {
-    match load_ports_from_path(&path) {
-        Ok(v) if !v.is_empty() => v,
-        _ => default_ports(),
+/// What to scan when the ports file is missing or lists no ports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PortsFallback {
+    /// The built-in `default_ports` list.
+    #[default]
+    Default,
+    /// The smaller `quick_ports` list.
+    Quick,
+    /// No fallback: a missing or empty file is an error.
+    None,
+}
+
+impl PortsFallback {
+    /// The list to scan instead, or `None` for `PortsFallback::None`.
+    pub fn ports(self) -> Option<Vec<u16>> {
+        match self {
+            PortsFallback::Default => Some(default_ports()),
+            PortsFallback::Quick => Some(quick_ports()),
+            PortsFallback::None => None,
+        }
+    }
+}
+
+impl FromStr for PortsFallback {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "default" => Ok(PortsFallback::Default),
+            "quick" => Ok(PortsFallback::Quick),
+            "none" => Ok(PortsFallback::None),
+            other => bail!("unknown ports fallback: {other} (expected default, quick, or none)"),
+        }
     }
 }
 
+/// Load a ports list from a file, or `fallback`'s list if the file is missing or empty. With
+/// `PortsFallback::None` that is an error instead; a file that doesn't parse always is.
+pub fn load_ports_or_default(path: impl AsRef<Path>, fallback: PortsFallback) -> Result<Vec<u16>> {
+    let path = path.as_ref();
+    let list = if path.exists() {
+        load_ports_from_path(path)
+            .with_context(|| format!("invalid ports file {}", path.display()))?
+    } else {
+        Vec::new()
+    };
+    if !list.is_empty() {
+        return Ok(list);
+    }
+    fallback.ports().with_context(|| {
+        format!(
+            "ports file {} is missing or lists no ports (no fallback)",
+            path.display()
+        )
+    })
+}
+
 /// A conservative default list of commonly used TCP ports.
 /// This list is intentionally small-but-useful and safe for LAN scanning.
 pub fn default_ports() -> Vec<u16> {
@@ -95,8 +242,172 @@ pub fn quick_ports() -> Vec<u16> {
     Q.to_vec()
 }
 
+/// Web servers and admin consoles, plain and TLS; each gets the HTTP or TLS probe.
+const WEB_PORTS: &[u16] = &[
+    80, 81, 443, 591, 8000, 8001, 8008, 8080, 8081, 8088, 8443, 8888, 9443,
+];
+
+/// Databases, caches, and search engines.
+const DB_PORTS: &[u16] = &[
+    1433, 1434, 1521, 2483, 3306, 5432, 5984, 6379, 6380, 7000, 8529, 9042, 9200, 11211, 27017,
+    27018,
+];
+
+/// A named starting point for a scan: a port set plus the opt-in probes that suit it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ScanProfile {
+    /// `quick_ports`, no opt-in probes.
+    Quick,
+    /// `default_ports` with the Redis, SMB, and Kafka handshakes.
+    Full,
+    /// HTTP(S) ports only.
+    Web,
+    /// Database ports with the Redis PING.
+    Db,
+}
+
+impl ScanProfile {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ScanProfile::Quick => "quick",
+            ScanProfile::Full => "full",
+            ScanProfile::Web => "web",
+            ScanProfile::Db => "db",
+        }
+    }
+}
+
+impl FromStr for ScanProfile {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "quick" => Ok(ScanProfile::Quick),
+            "full" => Ok(ScanProfile::Full),
+            "web" => Ok(ScanProfile::Web),
+            "db" => Ok(ScanProfile::Db),
+            other => bail!("unknown profile: {other} (expected quick, full, web, or db)"),
+        }
+    }
+}
+
+/// The ports a profile scans and the opt-in probes it turns on.
+pub fn profile_preset(profile: ScanProfile) -> (Vec<u16>, ProbeSet) {
+    match profile {
+        ScanProfile::Quick => (quick_ports(), ProbeSet::default()),
+        ScanProfile::Full => (
+            default_ports(),
+            ProbeSet {
+                redis: true,
+                smb: true,
+                kafka: true,
+                ..ProbeSet::default()
+            },
+        ),
+        ScanProfile::Web => (WEB_PORTS.to_vec(), ProbeSet::default()),
+        ScanProfile::Db => (
+            DB_PORTS.to_vec(),
+            ProbeSet {
+                redis: true,
+                ..ProbeSet::default()
+            },
+        ),
+    }
+}
+
+/// The inverse of `parse_ports_list`: sorted, deduplicated ports with consecutive runs collapsed,
+/// in nmap `-p` form (`22,80,443,8000-8002`).
+pub fn format_ranges(ports: &[u16]) -> String {
+    let mut sorted = ports.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    let mut parts: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < sorted.len() {
+        let start = sorted[i];
+        let mut end = start;
+        while sorted.get(i + 1).is_some_and(|&next| next == end + 1) {
+            end += 1;
+            i += 1;
+        }
+        parts.push(if start == end {
+            start.to_string()
+        } else {
+            format!("{start}-{end}")
+        });
+        i += 1;
+    }
+    parts.join(",")
+}
+
+/// TCP ports by how often they're found open in the wild (nmap's frequency ranking, top first),
+/// for `PortOrder::CommonFirst`.
+pub const COMMON_PORT_RANK: &[u16] = &[
+    80, 23, 443, 21, 22, 25, 3389, 110, 445, 139, 143, 53, 135, 3306, 8080, 1723, 111, 995, 993,
+    5900, 1025, 587, 8888, 199, 1720, 465, 548, 113, 81, 6001, 10000, 514, 5060, 179, 1026, 2000,
+    8443, 8000, 32768, 554, 26, 1433, 49152, 2001, 515, 8008, 49154, 1027, 5666, 646, 5000, 5631,
+    631, 49153, 8081, 2049, 88, 79, 5800, 106, 2121, 1110, 49155, 6000, 513, 990, 5357, 427, 49156,
+    543, 544, 5101, 144, 7, 389,
+];
+
+/// In which order a scan works through its sockets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PortOrder {
+    /// Host by host, each host's ports in the order given.
+    #[default]
+    AsGiven,
+    /// Port by port across all hosts, most commonly open ports first (`COMMON_PORT_RANK`), so
+    /// an interrupted scan has already covered the likeliest findings.
+    CommonFirst,
+    /// Host by host, the ports in a shuffled order drawn from the scan's seed
+    /// (`ScanOptions::seed`), so the same seed repeats the same work order.
+    Random,
+}
+
+impl FromStr for PortOrder {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "as-given" => Ok(PortOrder::AsGiven),
+            "common-first" => Ok(PortOrder::CommonFirst),
+            "random" => Ok(PortOrder::Random),
+            other => {
+                bail!("unknown port order: {other} (expected as-given, common-first, or random)")
+            }
+        }
+    }
+}
+
+/// `ports` reordered by `COMMON_PORT_RANK`; unranked ports follow in their original order.
+pub fn common_first(ports: &[u16]) -> Vec<u16> {
+    let rank = |p: &u16| {
+        COMMON_PORT_RANK
+            .iter()
+            .position(|r| r == p)
+            .unwrap_or(usize::MAX)
+    };
+    let mut sorted = ports.to_vec();
+    sorted.sort_by_key(rank);
+    sorted
+}
+
+/// `ports` shuffled by a `ScanRng` started from `seed`, for `PortOrder::Random`.
+pub fn shuffled(ports: &[u16], seed: u64) -> Vec<u16> {
+    let mut order = ports.to_vec();
+    ScanRng::new(seed).shuffle(&mut order);
+    order
+}
+
+/// A decimal or `0x`-prefixed hex port number in 1..=65535.
 fn parse_port_str(s: &str) -> Result<u16> {
-    let val: u32 = s.parse::<u32>().map_err(|e| anyhow::anyhow!(e))?;
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => s.parse::<u32>(),
+    };
+    let val = parsed.map_err(|e| anyhow::anyhow!(e))?;
     if val == 0 || val > 65535 {
         bail!("port out of range: {val}");
     }
@@ -114,6 +425,37 @@ mod tests {
         assert_eq!(ports, vec![80, 22, 443]);
     }
 
+    #[test]
+    fn parse_hex_and_open_ended_ranges() {
+        assert_eq!(parse_ports_str("0x50\n0X1F90\n").unwrap(), [80, 8080]);
+        let low = parse_ports_str("-1024").unwrap();
+        assert_eq!((low.len(), low[0], low[1023]), (1024, 1, 1024));
+        let high = parse_ports_str("60000-").unwrap();
+        assert_eq!((high.len(), high[0]), (5536, 60000));
+        assert_eq!(high.last(), Some(&65535));
+        assert_eq!(parse_ports_str("0x10-0x12").unwrap(), [16, 17, 18]);
+        assert!(parse_ports_str("-").is_err());
+        assert!(parse_ports_str("0x0").is_err());
+        assert!(parse_ports_str("0x10000").is_err());
+        assert!(
+            parse_ports_str("0x20-10").is_err(),
+            "start > end still applies"
+        );
+    }
+
+    #[test]
+    fn random_order_is_a_seeded_permutation() {
+        let ports: Vec<u16> = (1..=64).collect();
+        let order = shuffled(&ports, 7);
+        assert_eq!(order, shuffled(&ports, 7));
+        assert_ne!(order, ports);
+        assert_ne!(order, shuffled(&ports, 8));
+        let mut sorted = order.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, ports);
+        assert_eq!("random".parse::<PortOrder>().unwrap(), PortOrder::Random);
+    }
+
     #[test]
     fn parse_ranges_and_dedup() {
         let input = "8000-8002\n80\n8001\n";
@@ -142,6 +484,13 @@ mod tests {
         assert!(err.is_err());
     }
 
+    #[test]
+    fn parse_inline_list() {
+        let ports = parse_ports_list("22, 80,443 8000-8002").unwrap();
+        assert_eq!(ports, vec![22, 80, 443, 8000, 8001, 8002]);
+        assert!(parse_ports_list("22,abc").is_err());
+    }
+
     #[test]
     fn default_has_common_ports() {
         let d = default_ports();
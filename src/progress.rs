@@ -0,0 +1,117 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Smoothed completion-rate estimator over a sliding window of `(time, done)` samples.
+///
+/// Feed it the cumulative `scanned_done` counter as often as convenient; the rate is the
+/// slope between the oldest and newest samples in the window, which avoids the wild swings
+/// of instantaneous deltas.
+#[derive(Debug, Clone)]
+pub struct RateTracker {
+    window: Duration,
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl RateTracker {
+    /// Default smoothing window.
+    pub const DEFAULT_WINDOW: Duration = Duration::from_secs(5);
+    /// Minimum span between samples before a rate is reported.
+    const MIN_SPAN: Duration = Duration::from_millis(500);
+
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Record the cumulative completed count observed now.
+    pub fn record(&mut self, done: u64) {
+        self.record_at(Instant::now(), done);
+    }
+
+    fn record_at(&mut self, at: Instant, done: u64) {
+        self.samples.push_back((at, done));
+        // Keep one sample older than the window so the slope always spans it.
+        while self.samples.len() > 2 && at.duration_since(self.samples[1].0) >= self.window {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Completions per second, or `None` while there isn't enough history yet.
+    pub fn rate_per_sec(&self) -> Option<f64> {
+        let (t0, d0) = *self.samples.front()?;
+        let (t1, d1) = *self.samples.back()?;
+        let span = t1.duration_since(t0);
+        if span < Self::MIN_SPAN || d1 <= d0 {
+            return None;
+        }
+        Some((d1 - d0) as f64 / span.as_secs_f64())
+    }
+
+    /// Estimated seconds until `done` reaches `total`, or `None` if the rate is unknown.
+    pub fn eta_seconds(&self, done: u64, total: u64) -> Option<u64> {
+        let rate = self.rate_per_sec()?;
+        Some((total.saturating_sub(done) as f64 / rate).ceil() as u64)
+    }
+
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+}
+
+impl Default for RateTracker {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_WINDOW)
+    }
+}
+
+/// How long `total` connects take if every one of them runs into `timeout`, `concurrency` at a
+/// time: the scan's upper bound when nothing answers (probes on open ports come on top).
+pub fn estimate_worst_case(total: u64, concurrency: usize, timeout: Duration) -> Duration {
+    let waves = total.div_ceil(concurrency.max(1) as u64);
+    timeout.saturating_mul(u32::try_from(waves).unwrap_or(u32::MAX))
+}
+
+/// Format seconds as `m:ss` for progress output.
+pub fn format_eta(seconds: u64) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_unknown_until_enough_history() {
+        let mut r = RateTracker::default();
+        let t = Instant::now();
+        r.record_at(t, 0);
+        assert_eq!(r.rate_per_sec(), None);
+        r.record_at(t + Duration::from_millis(100), 50);
+        assert_eq!(r.rate_per_sec(), None);
+        r.record_at(t + Duration::from_secs(2), 200);
+        assert_eq!(r.rate_per_sec(), Some(100.0));
+        assert_eq!(r.eta_seconds(200, 1200), Some(10));
+    }
+
+    #[test]
+    fn old_samples_fall_out_of_window() {
+        let mut r = RateTracker::new(Duration::from_secs(2));
+        let t = Instant::now();
+        r.record_at(t, 0);
+        r.record_at(t + Duration::from_secs(1), 1000);
+        r.record_at(t + Duration::from_secs(5), 1010);
+        r.record_at(t + Duration::from_secs(6), 1020);
+        // Only the slow tail remains: 20 items over 5s.
+        assert_eq!(r.rate_per_sec(), Some(4.0));
+    }
+
+    #[test]
+    fn worst_case_counts_timeout_waves() {
+        let timeout = Duration::from_millis(400);
+        assert_eq!(estimate_worst_case(2540, 1000, timeout), timeout * 3);
+        assert_eq!(estimate_worst_case(0, 1000, timeout), Duration::ZERO);
+        assert_eq!(estimate_worst_case(5, 0, timeout), timeout * 5);
+    }
+}
@@ -0,0 +1,194 @@
+//! Scanning through an HTTP proxy (`--proxy http://host:port`): each connect opens a connection
+//! to the proxy and asks it to `CONNECT ip:port`. Once the proxy answers 2xx the socket is a
+//! plain tunnel to the target, so the banner, TLS, and protocol probes run over it unchanged.
+use crate::url::parse_url;
+use anyhow::{bail, Context, Result};
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Longest proxy response head read before the reply is rejected.
+const MAX_RESPONSE_HEAD: usize = 8 * 1024;
+
+/// An HTTP proxy that supports `CONNECT`, with optional Basic credentials.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpProxy {
+    /// The proxy's address, resolved once when the proxy is configured.
+    pub addr: SocketAddr,
+    /// `Proxy-Authorization: Basic` value (base64 of `user:pass`).
+    auth: Option<String>,
+}
+
+impl HttpProxy {
+    /// Parse `http://host:port` (the scheme may be left out; the port defaults to 80) and resolve
+    /// the host.
+    pub fn parse(url: &str) -> Result<Self> {
+        let url = url.trim();
+        let url = if url.contains("://") {
+            url.to_string()
+        } else {
+            format!("http://{url}")
+        };
+        let parts = parse_url(&url)?;
+        if parts.tls {
+            bail!("proxy must be http://, not https:// (the tunnel itself carries TLS): {url}");
+        }
+        let addr = (parts.host.as_str(), parts.port)
+            .to_socket_addrs()
+            .with_context(|| format!("failed to resolve proxy {}", parts.host))?
+            .next()
+            .with_context(|| format!("proxy {} did not resolve to any address", parts.host))?;
+        Ok(Self { addr, auth: None })
+    }
+
+    /// Send Basic credentials (`user:pass`) with every `CONNECT`.
+    pub fn with_auth(mut self, user_pass: Option<&str>) -> Self {
+        self.auth = user_pass.map(|up| base64(up.as_bytes()));
+        self
+    }
+
+    /// Ask the proxy, over `stream` (a fresh connection to it), to open a tunnel to `target`.
+    /// The proxy's error replies map onto the errors a direct connect would give: 504 is a
+    /// timeout and 407 rejected credentials. 502/503 stay generic errors (filtered): proxies send
+    /// them for dead and unreachable hosts as well as refused ports, so they can't mark a port
+    /// closed or its host live.
+    pub async fn tunnel(&self, mut stream: TcpStream, target: SocketAddr) -> io::Result<TcpStream> {
+        let mut req = format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n");
+        if let Some(auth) = &self.auth {
+            req.push_str(&format!("Proxy-Authorization: Basic {auth}\r\n"));
+        }
+        req.push_str("\r\n");
+        stream.write_all(req.as_bytes()).await?;
+        let head = read_response_head(&mut stream).await?;
+        match status_code(&head) {
+            Some(200..=299) => Ok(stream),
+            Some(status) => Err(io::Error::new(
+                error_kind(status),
+                format!("proxy refused CONNECT {target}: HTTP {status}"),
+            )),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "proxy sent a malformed CONNECT reply",
+            )),
+        }
+    }
+}
+
+fn error_kind(status: u16) -> io::ErrorKind {
+    match status {
+        504 => io::ErrorKind::TimedOut,
+        407 => io::ErrorKind::PermissionDenied,
+        _ => io::ErrorKind::Other,
+    }
+}
+
+/// Read the reply up to the blank line, one byte at a time so nothing the target sends right
+/// after it (an SSH banner, say) is consumed.
+async fn read_response_head(stream: &mut TcpStream) -> io::Result<String> {
+    let mut head = Vec::with_capacity(128);
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_RESPONSE_HEAD {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "proxy reply head too long",
+            ));
+        }
+        let byte = stream.read_u8().await?;
+        head.push(byte);
+    }
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// `HTTP/1.1 200 Connection established` -> 200.
+fn status_code(head: &str) -> Option<u16> {
+    let line = head.lines().next()?;
+    let mut parts = line.split_whitespace();
+    if !parts.next()?.starts_with("HTTP/") {
+        return None;
+    }
+    parts.next()?.parse().ok()
+}
+
+/// Standard base64 with padding (RFC 4648), for the Basic credentials.
+fn base64(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 63] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn parses_proxy_urls() {
+        let p = HttpProxy::parse("http://127.0.0.1:3128").unwrap();
+        assert_eq!(p.addr, "127.0.0.1:3128".parse().unwrap());
+        assert_eq!(
+            HttpProxy::parse("127.0.0.1").unwrap().addr,
+            "127.0.0.1:80".parse().unwrap()
+        );
+        assert!(HttpProxy::parse("https://127.0.0.1:3128").is_err());
+    }
+
+    #[test]
+    fn encodes_basic_credentials() {
+        assert_eq!(base64(b"user:pass"), "dXNlcjpwYXNz");
+        assert_eq!(base64(b"a"), "YQ==");
+        assert_eq!(base64(b"ab"), "YWI=");
+        assert_eq!(status_code("HTTP/1.1 407 Proxy Auth\r\n\r\n"), Some(407));
+    }
+
+    #[tokio::test]
+    async fn tunnels_through_connect_and_keeps_the_banner() {
+        let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = proxy.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut s, _) = proxy.accept().await.unwrap();
+            let mut buf = vec![0u8; 512];
+            let n = s.read(&mut buf).await.unwrap();
+            let req = String::from_utf8_lossy(&buf[..n]).into_owned();
+            // Reply and the target's banner arrive in one segment.
+            s.write_all(b"HTTP/1.1 200 Connection established\r\n\r\nSSH-2.0-test\r\n")
+                .await
+                .unwrap();
+            req
+        });
+        let proxy = HttpProxy::parse(&format!("http://{addr}"))
+            .unwrap()
+            .with_auth(Some("user:pass"));
+        let stream = TcpStream::connect(proxy.addr).await.unwrap();
+        let target: SocketAddr = "10.0.0.5:22".parse().unwrap();
+        let mut tunnel = proxy.tunnel(stream, target).await.unwrap();
+        let mut banner = String::new();
+        tunnel.read_to_string(&mut banner).await.unwrap();
+        assert_eq!(banner, "SSH-2.0-test\r\n");
+        let req = server.await.unwrap();
+        assert!(req.starts_with("CONNECT 10.0.0.5:22 HTTP/1.1\r\n"), "{req}");
+        assert!(req.contains("Proxy-Authorization: Basic dXNlcjpwYXNz\r\n"));
+    }
+
+    #[test]
+    fn unreachable_replies_are_not_refusals() {
+        assert_ne!(error_kind(503), io::ErrorKind::ConnectionRefused);
+        assert_ne!(error_kind(502), io::ErrorKind::ConnectionRefused);
+        assert_eq!(error_kind(504), io::ErrorKind::TimedOut);
+    }
+}
@@ -0,0 +1,134 @@
+//! Hostname resolution for target lists: lookups run concurrently behind their own semaphore,
+//! each with a timeout so a dead name can't stall the rest, and every answer (failures included)
+//! is cached so repeated names are looked up once.
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time;
+
+/// Lookups in flight at once unless configured otherwise.
+pub const DEFAULT_CONCURRENCY: usize = 32;
+/// Time allowed for one lookup unless configured otherwise.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A shared, bounded resolver; clones share the semaphore and the cache.
+#[derive(Clone, Debug)]
+pub struct Resolver {
+    permits: Arc<Semaphore>,
+    timeout: Duration,
+    cache: Arc<Mutex<HashMap<String, Answer>>>,
+}
+
+/// Addresses a name resolved to, or the error message of a failed lookup.
+type Answer = Result<Vec<IpAddr>, String>;
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new(DEFAULT_CONCURRENCY, DEFAULT_TIMEOUT)
+    }
+}
+
+impl Resolver {
+    pub fn new(concurrency: usize, timeout: Duration) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(concurrency.max(1))),
+            timeout,
+            cache: Arc::default(),
+        }
+    }
+
+    /// Every address `name` resolves to, from the cache when it was looked up before.
+    pub async fn lookup(&self, name: &str) -> Result<Vec<IpAddr>> {
+        if let Some(cached) = self.cached(name) {
+            return cached.map_err(|e| anyhow!(e));
+        }
+        let answer = {
+            let _permit = self
+                .permits
+                .acquire()
+                .await
+                .expect("resolver semaphore is never closed");
+            match time::timeout(self.timeout, tokio::net::lookup_host((name, 0))).await {
+                Ok(Ok(addrs)) => Ok(addrs.map(|sa| sa.ip()).collect::<Vec<_>>()),
+                Ok(Err(e)) => Err(format!("invalid target {name}: {e}")),
+                Err(_) => Err(format!(
+                    "hostname {name} did not resolve within {} ms",
+                    self.timeout.as_millis()
+                )),
+            }
+        };
+        self.cache
+            .lock()
+            .expect("resolver cache poisoned")
+            .insert(name.to_string(), answer.clone());
+        answer.map_err(|e| anyhow!(e))
+    }
+
+    /// Resolve a hostname target to one address, preferring IPv4 since only IPv4 is scanned by
+    /// default.
+    pub async fn resolve(&self, name: &str) -> Result<IpAddr> {
+        let addrs = self.lookup(name).await?;
+        addrs
+            .iter()
+            .copied()
+            .find(IpAddr::is_ipv4)
+            .or_else(|| addrs.first().copied())
+            .ok_or_else(|| anyhow!("hostname {name} did not resolve to any address"))
+    }
+
+    /// `resolve` every name concurrently (up to the pool's limit), answers in `names` order.
+    /// Repeated names are looked up once.
+    pub async fn resolve_all(&self, names: &[String]) -> Vec<Result<IpAddr>> {
+        let mut set = JoinSet::new();
+        let mut queued = std::collections::HashSet::new();
+        for name in names {
+            if queued.insert(name.as_str()) && self.cached(name).is_none() {
+                let resolver = self.clone();
+                let name = name.clone();
+                set.spawn(async move {
+                    let _ = resolver.lookup(&name).await;
+                });
+            }
+        }
+        while set.join_next().await.is_some() {}
+        let mut out = Vec::with_capacity(names.len());
+        for name in names {
+            out.push(self.resolve(name).await);
+        }
+        out
+    }
+
+    fn cached(&self, name: &str) -> Option<Answer> {
+        self.cache
+            .lock()
+            .expect("resolver cache poisoned")
+            .get(name)
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn resolves_in_order_and_caches_failures() {
+        let resolver = Resolver::new(2, Duration::from_secs(5));
+        let names: Vec<String> = ["localhost", "no-such-host.invalid", "localhost"]
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+        let answers = resolver.resolve_all(&names).await;
+        let localhost: IpAddr = "127.0.0.1".parse().unwrap();
+        assert_eq!(answers[0].as_ref().unwrap(), &localhost);
+        assert!(answers[1].is_err());
+        assert_eq!(answers[2].as_ref().unwrap(), &localhost);
+        let cache = resolver.cache.lock().unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache["no-such-host.invalid"].is_err());
+    }
+}
@@ -0,0 +1,120 @@
+//! Severity rules for open ports: what a finding means given its service and probe results.
+//! Unauthenticated access to data or code execution ranks highest; a port that is merely open
+//! is informational.
+use anyhow::{bail, Error};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// How serious an open port is, ordered from `Info` to `Critical`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "info" => Ok(Severity::Info),
+            "low" => Ok(Severity::Low),
+            "medium" => Ok(Severity::Medium),
+            "high" => Ok(Severity::High),
+            "critical" => Ok(Severity::Critical),
+            other => {
+                bail!("unknown severity: {other} (expected info, low, medium, high, or critical)")
+            }
+        }
+    }
+}
+
+/// Rate an open port from its service name and banner (as produced by the scanner's probes).
+pub fn assess(port: u16, service: Option<&str>, banner: Option<&str>) -> Severity {
+    let banner = banner.unwrap_or("");
+    let words: Vec<&str> = banner.split(' ').collect();
+    let has = |w: &str| words.contains(&w);
+    match service.unwrap_or("") {
+        // Answering PING without AUTH means anyone can read and write the keyspace.
+        "redis" if banner == "redis PONG" => Severity::Critical,
+        "docker" if has("auth=disabled") => Severity::Critical,
+        "docker" => Severity::Medium,
+        "elasticsearch" | "opensearch" if has("auth=disabled") => Severity::High,
+        "snmp/udp" if banner.contains("(default community)") => Severity::High,
+        "snmp/udp" => Severity::Medium,
+        "smb" if has("signing=disabled") || has("signing=enabled") => Severity::Medium,
+        "dns" if has("recursion=open") => Severity::Medium,
+        // Cleartext logins.
+        "telnet" | "ftp" => Severity::Medium,
+        "smb" | "redis" | "mysql" | "postgresql" | "mongodb" | "mssql" | "oracle" => Severity::Low,
+        _ if matches!(port, 3389 | 5900 | 5901) => Severity::Low,
+        _ => Severity::Info,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_findings_get_expected_severity() {
+        assert_eq!(
+            assess(6379, Some("redis"), Some("redis PONG")),
+            Severity::Critical
+        );
+        assert_eq!(
+            assess(
+                6379,
+                Some("redis"),
+                Some("-NOAUTH Authentication required.")
+            ),
+            Severity::Low
+        );
+        assert_eq!(
+            assess(
+                2375,
+                Some("docker"),
+                Some("docker 24.0.7 api=1.43 auth=disabled")
+            ),
+            Severity::Critical
+        );
+        assert_eq!(
+            assess(23, Some("telnet"), Some("telnet: login:")),
+            Severity::Medium
+        );
+        assert_eq!(
+            assess(80, Some("http"), Some("HTTP title=\"Intranet\"")),
+            Severity::Info
+        );
+    }
+
+    #[test]
+    fn severities_order_and_parse() {
+        assert!(Severity::Critical > Severity::High && Severity::Low > Severity::Info);
+        assert_eq!("HIGH".parse::<Severity>().unwrap(), Severity::High);
+        assert!("urgent".parse::<Severity>().is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! A seedable RNG for scan features that need randomness (shuffling, jitter), so a run can be
+//! replayed by passing the same `--seed`. `--order random` draws its port order from it.
+//! SplitMix64: tiny, fast, and plenty for scheduling decisions; not for anything cryptographic.
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone)]
+pub struct ScanRng {
+    seed: u64,
+    state: u64,
+}
+
+impl ScanRng {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, state: seed }
+    }
+
+    /// The seed this generator started from; pass it back as `--seed` to reproduce a run.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n` (`0` when `n` is 0), without modulo bias.
+    pub fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            return 0;
+        }
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+
+    /// Fisher-Yates shuffle in place.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+
+    /// A random delay in `0..=max`, at millisecond resolution.
+    pub fn jitter(&mut self, max: Duration) -> Duration {
+        let ms = u64::try_from(max.as_millis()).unwrap_or(u64::MAX);
+        Duration::from_millis(self.below(ms.saturating_add(1)))
+    }
+}
+
+/// A fresh seed for runs that didn't ask for one: the process's random hasher keys mixed with the
+/// clock, so back-to-back runs differ.
+pub fn entropy_seed() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    hasher.write_u128(nanos);
+    hasher.write_u32(std::process::id());
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_the_same_order_and_jitter() {
+        let run = |seed| {
+            let mut rng = ScanRng::new(seed);
+            let mut order: Vec<u16> = (1..=50).collect();
+            rng.shuffle(&mut order);
+            let delays: Vec<Duration> = (0..5)
+                .map(|_| rng.jitter(Duration::from_millis(100)))
+                .collect();
+            (order, delays)
+        };
+        assert_eq!(run(42), run(42));
+        assert_ne!(run(42).0, run(43).0);
+        let (mut order, delays) = run(7);
+        assert!(delays.iter().all(|d| *d <= Duration::from_millis(100)));
+        order.sort_unstable();
+        assert_eq!(order, (1..=50).collect::<Vec<u16>>());
+    }
+}
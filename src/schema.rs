@@ -0,0 +1,121 @@
+//! Loading results JSON written by any schema version.
+//!
+//! `ScanResults` rejects unknown fields, so a document is first read as untyped JSON, upgraded one
+//! version at a time by `migrate`, and only then deserialized. Files from a newer build are refused
+//! rather than silently misread.
+use crate::types::{ScanResults, SCHEMA_VERSION};
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+/// Parse a results document of any known version into the current `ScanResults`.
+pub fn parse_results(s: &str) -> Result<ScanResults> {
+    let doc: Value = serde_json::from_str(s)?;
+    Ok(serde_json::from_value(migrate(doc)?)?)
+}
+
+/// Read and parse a results file written by `--output` (any known version).
+pub fn load_results(path: impl AsRef<Path>) -> Result<ScanResults> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("failed to read results: {}", path.display()))?;
+    parse_results(&text).with_context(|| format!("invalid results file: {}", path.display()))
+}
+
+/// Upgrade a results document to `SCHEMA_VERSION`. Documents without `version` are version 1.
+pub fn migrate(mut doc: Value) -> Result<Value> {
+    let Some(obj) = doc.as_object_mut() else {
+        bail!("results must be a JSON object");
+    };
+    let mut version = match obj.get("version") {
+        None => 1,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .context("`version` must be a non-negative integer")?,
+    };
+    if version > SCHEMA_VERSION {
+        bail!("results schema version {version} is newer than supported version {SCHEMA_VERSION}");
+    }
+    while version < SCHEMA_VERSION {
+        match version {
+            // v1 -> v2: document gains `version` and `generated_at` (unknown for old files).
+            1 => {
+                obj.insert("generated_at".into(), json!(""));
+            }
+            // v2 -> v3: optional `meta` block; nothing to fill in.
+            2 => {}
+            // v3 -> v4: `cancelled` flag, false for every older (complete) file.
+            3 => {
+                obj.insert("cancelled".into(), json!(false));
+            }
+            // v4 -> v5: optional per-entry `advertised`; nothing to fill in.
+            4 => {}
+            // v5 -> v6: optional per-entry `host` (vhost name); nothing to fill in.
+            5 => {}
+            // v6 -> v7: per-entry `protocol`, omitted (TCP) for every older entry.
+            6 => {}
+            // v7 -> v8: optional per-entry `severity`; older findings stay unrated.
+            7 => {}
+            // v8 -> v9: optional per-entry `tls_valid` (--tls-verify); unchecked in older files.
+            8 => {}
+            // v9 -> v10: per-entry `tls` flag; older files never recorded it, so it reads false.
+            9 => {}
+            // v10 -> v11: optional per-entry `alpn`; older files never negotiated it.
+            10 => {}
+            // v11 -> v12: `closed_count`/`filtered_count`; older scans didn't tell them apart and
+            // read as 0.
+            11 => {}
+            // v12 -> v13: `timed_out` flag (--max-duration); omitted, so false, in older files.
+            12 => {}
+            // v13 -> v14: per-entry `baseline` and `baseline_missing` (--baseline); older files
+            // were never compared.
+            13 => {}
+            // v14 -> v15: `live_hosts`; older scans dropped refusals, so it reads empty.
+            14 => {}
+            // v15 -> v16: optional `timing` (throughput and connect/probe time); not measured
+            // before.
+            15 => {}
+            _ => unreachable!("no migration from schema version {version}"),
+        }
+        version += 1;
+        obj.insert("version".into(), json!(version));
+    }
+    Ok(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_unversioned_files() {
+        let old = r#"{"scanned_total":2,"scanned_done":2,"open_count":1,"entries":[
+            {"ip":"10.0.0.1","port":22,"open":true,"latency_ms":3,"service":"ssh","banner":null,"timestamp":"t"}]}"#;
+        let r = parse_results(old).unwrap();
+        assert_eq!(r.version, SCHEMA_VERSION);
+        assert_eq!(r.entries[0].port, 22);
+    }
+
+    #[test]
+    fn current_output_round_trips() {
+        let r = ScanResults {
+            generated_at: "2026-01-01T00:00:00Z".into(),
+            ..Default::default()
+        };
+        let back = parse_results(&serde_json::to_string(&r).unwrap()).unwrap();
+        assert_eq!(back.generated_at, r.generated_at);
+    }
+
+    #[test]
+    fn rejects_newer_and_unknown_shapes() {
+        let future = format!(
+            r#"{{"version":{},"generated_at":"","scanned_total":0,"scanned_done":0,"open_count":0,"entries":[]}}"#,
+            SCHEMA_VERSION + 1
+        );
+        assert!(parse_results(&future).is_err());
+        let typo = r#"{"scanned_total":0,"scanned_done":0,"open_count":0,"entries":[],"opne":1}"#;
+        assert!(parse_results(typo).is_err());
+    }
+}
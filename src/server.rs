@@ -1,47 +1,146 @@
-use std::{net::IpAddr, sync::Arc, time::Duration};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
-use axum::http::header::{CACHE_CONTROL, EXPIRES, PRAGMA};
+use axum::http::header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE, EXPIRES, PRAGMA};
 use axum::http::HeaderValue;
 use axum::{
-    extract::State,
+    extract::{rejection::JsonRejection, Query, State},
     http::StatusCode,
-    response::IntoResponse,
+    response::{Html, IntoResponse},
     routing::{get, post},
     Json, Router,
 };
-use ipnet::IpNet;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use tokio::sync::RwLock;
 use tokio_util::sync::CancellationToken;
-use tower_http::services::ServeDir;
+use tower_http::services::{ServeDir, ServeFile};
 
 use crate::{
+    alert, export,
+    filter::{self, ResultFilter},
     netdetect, ports,
-    scanner::{self, SharedProgress},
-    types::ScanResults,
+    scanner::{self, ScanOptions, SharedProgress},
+    types::{ScanMeta, ScanPlan, ScanResults, ScanTiming, SCHEMA_VERSION},
+    webhook::{self, WebhookConfig},
 };
 
+/// Where the UI's static files are served from unless configured otherwise.
+pub const DEFAULT_UI_DIR: &str = "ui";
+
+/// Server-wide settings fixed at startup.
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Notified with a summary each time a scan finishes.
+    pub webhook: Option<WebhookConfig>,
+    /// Static UI files; paths that match no file get `index.html` (single-page app routing).
+    pub ui_dir: PathBuf,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            webhook: None,
+            ui_dir: PathBuf::from(DEFAULT_UI_DIR),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct AppState {
     inner: Arc<RwLock<ServerState>>, // shared mutable state for progress/results
+    config: Arc<ServerConfig>,
 }
 
 #[derive(Debug)]
 struct ServerState {
     status: Status,
     results: Option<ScanResults>,
+    /// The completed scan before the latest one, with its id; kept so its report stays
+    /// downloadable while a newer scan runs.
+    previous: Option<(u64, ScanResults)>,
+    /// Port list stored by `POST /api/ports`, scanned by requests with `"uploaded_ports": true`.
+    uploaded_ports: Option<Vec<u16>>,
     progress: Option<SharedProgress>,
     cancel: Option<CancellationToken>,
+    last_id: u64, // id of the most recently started scan; 0 before the first
+}
+
+impl ServerState {
+    /// Record a new scan as the latest and return its id. Completed results move to `previous`
+    /// rather than being dropped.
+    fn begin_scan(
+        &mut self,
+        total: u64,
+        progress: SharedProgress,
+        cancel: CancellationToken,
+    ) -> u64 {
+        // Cancel any existing scan
+        if let Some(c) = self.cancel.replace(cancel) {
+            c.cancel();
+        }
+        if let (Some(done), Some(id)) = (self.results.take(), self.status.id) {
+            self.previous = Some((id, done));
+        }
+        self.last_id += 1;
+        self.status = Status {
+            id: Some(self.last_id),
+            total,
+            scanned: 0,
+            open: 0,
+            state: "running".into(),
+            eta_seconds: None,
+            indeterminate: false,
+        };
+        self.progress = Some(progress);
+        self.last_id
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub struct Status {
+    /// Id of the scan this describes, as returned by `POST /api/scan`; absent before the first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
     pub total: u64,
     pub scanned: u64,
     pub open: u64,
-    pub state: String, // "idle" | "running" | "done"
+    pub state: String, // "idle" | "running" | "paused" | "done"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eta_seconds: Option<u64>, // omitted until a scan rate is known
+    /// True while the total is still growing (streamed targets); `total` is then a lower bound.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub indeterminate: bool,
+}
+
+/// Entries per `GET /api/results` page when no `limit` is given, and the largest allowed.
+pub const DEFAULT_PAGE_LIMIT: usize = 1000;
+pub const MAX_PAGE_LIMIT: usize = 10_000;
+/// Response header carrying the number of (filtered) entries across all pages.
+const TOTAL_COUNT_HEADER: &str = "x-total-count";
+/// Response header naming the scan a `GET /api/results/previous` body came from.
+const SCAN_ID_HEADER: &str = "x-scan-id";
+
+/// `?id=`: the scan a status or results request is about; omitted = the latest.
+#[derive(Debug, Default, Deserialize)]
+pub struct ScanIdQuery {
+    #[serde(default)]
+    pub id: Option<u64>,
+}
+
+/// `?offset=&limit=` window over the sorted entries.
+#[derive(Debug, Default, Deserialize)]
+pub struct Page {
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default)]
+    pub limit: Option<usize>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -55,24 +154,196 @@ pub struct ScanRequest {
     pub concurrency: Option<usize>,
     #[serde(default)]
     pub timeout_ms: Option<u64>,
+    /// Stop the scan after this many milliseconds, keeping partial results flagged `timed_out`.
+    #[serde(default)]
+    pub max_duration_ms: Option<u64>,
     #[serde(default)]
     pub probe_redis: Option<bool>,
+    /// Opt-in probes to run by name (`scanner::OPTIONAL_PROBES`), e.g. `["redis", "kafka"]`.
+    #[serde(default)]
+    pub probe: Option<Vec<String>>,
+    /// `false` skips banner, TLS, and protocol probes for a lighter connect-only sweep.
+    #[serde(default)]
+    pub banners: Option<bool>,
+    /// Scan the list last stored by `POST /api/ports` instead of `ports`.
+    #[serde(default)]
+    pub uploaded_ports: Option<bool>,
     #[serde(default)]
     pub quick: Option<bool>, // if true and no ports provided, use quick port set
+    #[serde(default)]
+    pub dry_run: Option<bool>, // if true, return the ScanPlan without scanning
+    #[serde(default)]
+    pub force: Option<bool>, // if true, expand networks larger than a /16
+}
+
+/// Fields `POST /api/scan` understands; anything else is reported as unknown.
+const SCAN_REQUEST_FIELDS: &[&str] = &[
+    "targets",
+    "ports",
+    "exclude_ports",
+    "concurrency",
+    "timeout_ms",
+    "max_duration_ms",
+    "probe_redis",
+    "probe",
+    "banners",
+    "uploaded_ports",
+    "quick",
+    "dry_run",
+    "force",
+];
+/// Scan settings for requests that leave them out; `quick` caps the timeout further.
+const DEFAULT_REQUEST_CONCURRENCY: usize = 1000;
+const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 400;
+const QUICK_REQUEST_TIMEOUT_MS: u64 = 250;
+/// The scanner clamps concurrency to this anyway; a request asking for more is a client bug.
+const MAX_REQUEST_CONCURRENCY: usize = 5_000;
+const MAX_REQUEST_TIMEOUT_MS: u64 = 60_000;
+
+/// 422 body for a scan request that failed validation: each offending field and what's wrong
+/// with it, e.g. `{"errors":{"targets":"required","timeout_ms":"must be <= 60000"}}`.
+#[derive(Debug, Default, Serialize)]
+pub struct FieldErrors {
+    pub errors: BTreeMap<String, String>,
+}
+
+impl FieldErrors {
+    fn single(field: &str, message: impl Into<String>) -> Self {
+        let mut errors = Self::default();
+        errors.add(field, message);
+        errors
+    }
+
+    fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.errors.insert(field.to_string(), message.into());
+    }
+}
+
+impl IntoResponse for FieldErrors {
+    fn into_response(self) -> axum::response::Response {
+        (StatusCode::UNPROCESSABLE_ENTITY, Json(self)).into_response()
+    }
+}
+
+/// Check a scan request field by field, so a client learns about every problem at once instead
+/// of serde's first complaint about the whole body.
+pub fn validate_scan_request(body: Value) -> std::result::Result<ScanRequest, FieldErrors> {
+    let Value::Object(fields) = body else {
+        return Err(FieldErrors::single("body", "must be a JSON object"));
+    };
+    let mut errors = FieldErrors::default();
+    if !fields.contains_key("targets") {
+        errors.add("targets", "required");
+    }
+    for (name, value) in &fields {
+        if !SCAN_REQUEST_FIELDS.contains(&name.as_str()) {
+            errors.add(name, "unknown field");
+            continue;
+        }
+        // Type-check the field on its own, next to a placeholder for the required one.
+        let mut alone = Map::new();
+        alone.insert("targets".into(), Value::Array(Vec::new()));
+        alone.insert(name.clone(), value.clone());
+        if let Err(e) = serde_json::from_value::<ScanRequest>(Value::Object(alone)) {
+            errors.add(name, e.to_string());
+        }
+    }
+    if !errors.errors.is_empty() {
+        return Err(errors);
+    }
+    let req: ScanRequest = serde_json::from_value(Value::Object(fields))
+        .map_err(|e| FieldErrors::single("body", e.to_string()))?;
+    if req.targets.iter().all(|t| t.trim().is_empty()) {
+        errors.add("targets", "must not be empty");
+    }
+    if req.ports.contains(&0) {
+        errors.add("ports", "port 0 is not valid");
+    }
+    if req.uploaded_ports == Some(true) && !req.ports.is_empty() {
+        errors.add("uploaded_ports", "cannot be combined with ports");
+    }
+    match req.concurrency {
+        Some(0) => errors.add("concurrency", "must be >= 1"),
+        Some(n) if n > MAX_REQUEST_CONCURRENCY => errors.add(
+            "concurrency",
+            format!("must be <= {MAX_REQUEST_CONCURRENCY}"),
+        ),
+        _ => {}
+    }
+    match req.timeout_ms {
+        Some(0) => errors.add("timeout_ms", "must be >= 1"),
+        Some(n) if n > MAX_REQUEST_TIMEOUT_MS => {
+            errors.add("timeout_ms", format!("must be <= {MAX_REQUEST_TIMEOUT_MS}"))
+        }
+        _ => {}
+    }
+    if req.max_duration_ms == Some(0) {
+        errors.add("max_duration_ms", "must be >= 1");
+    }
+    if errors.errors.is_empty() {
+        Ok(req)
+    } else {
+        Err(errors)
+    }
+}
+
+/// The repository's `ui/`, compiled in so `--serve-ui` works from any install.
+const EMBEDDED_INDEX: &str = include_str!("../ui/index.html");
+const EMBEDDED_APP_JS: &str = include_str!("../ui/app.js");
+const EMBEDDED_STYLE_CSS: &str = include_str!("../ui/style.css");
+
+/// Serve the UI for every non-API path: from `ui_dir` when it has an `index.html` (so UI work
+/// needs no rebuild), else the embedded copy. Paths matching no file get `index.html`.
+fn with_ui(app: Router, ui_dir: &Path) -> Router {
+    if ui_dir.join("index.html").is_file() {
+        let static_svc = ServeDir::new(ui_dir)
+            .append_index_html_on_directories(true)
+            .fallback(ServeFile::new(ui_dir.join("index.html")));
+        return app.fallback_service(static_svc);
+    }
+    if ui_dir != Path::new(DEFAULT_UI_DIR) {
+        eprintln!(
+            "Warning: no index.html in {}; serving the built-in UI.",
+            ui_dir.display()
+        );
+    }
+    let asset = |content_type: &'static str, body: &'static str| {
+        get(move || async move { ([(CONTENT_TYPE, content_type)], body) })
+    };
+    app.route(
+        "/app.js",
+        asset("text/javascript; charset=utf-8", EMBEDDED_APP_JS),
+    )
+    .route(
+        "/style.css",
+        asset("text/css; charset=utf-8", EMBEDDED_STYLE_CSS),
+    )
+    .fallback(|| async { Html(EMBEDDED_INDEX) })
 }
 
 pub async fn spawn_server(bind: &str) -> Result<()> {
+    spawn_server_with_config(bind, ServerConfig::default()).await
+}
+
+pub async fn spawn_server_with_config(bind: &str, config: ServerConfig) -> Result<()> {
     let state = AppState {
+        config: Arc::new(config),
         inner: Arc::new(RwLock::new(ServerState {
             status: Status {
+                id: None,
                 total: 0,
                 scanned: 0,
                 open: 0,
                 state: "idle".into(),
+                eta_seconds: None,
+                indeterminate: false,
             },
             results: None,
+            previous: None,
+            uploaded_ports: None,
             progress: None,
             cancel: None,
+            last_id: 0,
         })),
     };
 
@@ -80,13 +351,21 @@ pub async fn spawn_server(bind: &str) -> Result<()> {
         .route("/status", get(get_status))
         .route("/scan", post(post_scan))
         .route("/cancel", post(post_cancel))
+        .route("/pause", post(post_pause))
+        .route("/resume", post(post_resume))
+        .route("/ports", post(post_ports))
         .route("/results", get(get_results))
+        .route("/results/previous", get(get_previous_results))
+        .route("/summary", get(get_summary))
+        .route("/capabilities", get(get_capabilities))
+        .route("/results.csv", get(get_results_csv))
+        .route("/results.xml", get(get_results_xml))
+        // Unknown API paths stay 404 instead of reaching the UI's index.html fallback.
+        .fallback(|| async { StatusCode::NOT_FOUND })
         .with_state(state.clone());
 
-    let static_svc = ServeDir::new("ui").append_index_html_on_directories(true);
-    let app = Router::new()
-        .nest("/api", api)
-        .fallback_service(static_svc)
+    let app = with_ui(Router::new().nest("/api", api), &state.config.ui_dir);
+    let app = app
         .layer(tower_http::set_header::SetResponseHeaderLayer::overriding(
             CACHE_CONTROL,
             HeaderValue::from_static("no-store, no-cache, must-revalidate"),
@@ -105,32 +384,293 @@ pub async fn spawn_server(bind: &str) -> Result<()> {
     Ok(())
 }
 
-async fn get_status(State(app): State<AppState>) -> impl IntoResponse {
+/// 404 when `?id=` names a scan other than the latest one (which replaced it).
+fn check_scan_id(s: &ServerState, q: &ScanIdQuery) -> Option<axum::response::Response> {
+    let id = q.id?;
+    (s.status.id != Some(id)).then(|| {
+        let msg = match s.status.id {
+            Some(latest) if id < latest => format!("scan {id} was replaced by scan {latest}"),
+            _ => format!("no scan with id {id}"),
+        };
+        (StatusCode::NOT_FOUND, msg).into_response()
+    })
+}
+
+async fn get_status(
+    State(app): State<AppState>,
+    Query(q): Query<ScanIdQuery>,
+) -> impl IntoResponse {
+    let s = app.inner.read().await;
+    if let Some(resp) = check_scan_id(&s, &q) {
+        return resp;
+    }
+    let mut out = live_status(&s);
+    // Each poll feeds the scan's rate tracker, so the ETA smooths over the poll interval.
+    out.eta_seconds = match s.progress.as_ref() {
+        Some(p) if s.status.state == "running" && !out.indeterminate => p.eta_seconds(out.total),
+        _ => None,
+    };
+    (StatusCode::OK, Json(out)).into_response()
+}
+
+async fn get_results(
+    State(app): State<AppState>,
+    Query(f): Query<ResultFilter>,
+    Query(page): Query<Page>,
+    Query(q): Query<ScanIdQuery>,
+) -> impl IntoResponse {
     let s = app.inner.read().await;
-    let (scanned, open) = if let Some(p) = s.progress.as_ref() {
+    if let Some(resp) = check_scan_id(&s, &q) {
+        return resp;
+    }
+    if let Some(res) = s.results.as_ref() {
+        let mut out = filter::filter(res, &f);
+        let total = out.entries.len();
+        let limit = page.limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+        out.entries = out
+            .entries
+            .into_iter()
+            .skip(page.offset)
+            .take(limit)
+            .collect();
         (
-            p.scanned_done.load(std::sync::atomic::Ordering::Relaxed),
-            p.open_count.load(std::sync::atomic::Ordering::Relaxed),
+            StatusCode::OK,
+            [(TOTAL_COUNT_HEADER, total.to_string())],
+            Json(out),
         )
+            .into_response()
     } else {
-        (s.status.scanned, s.status.open)
+        StatusCode::NO_CONTENT.into_response()
+    }
+}
+
+/// Answer to `POST /api/ports`: how many distinct ports the stored list holds.
+#[derive(Debug, Serialize)]
+struct PortsUpload {
+    count: usize,
+    /// Repeats that were collapsed.
+    duplicates: usize,
+}
+
+/// 422 body for an uploaded ports file with bad lines.
+#[derive(Debug, Serialize)]
+struct PortsUploadErrors {
+    errors: Vec<ports::PortParseError>,
+}
+
+/// Store a ports file (plain-text body, same format as `--ports`) for later scans to use via
+/// `"uploaded_ports": true`. Nothing is stored unless every line parses.
+async fn post_ports(State(app): State<AppState>, body: String) -> impl IntoResponse {
+    let report = ports::check_ports_str(&body);
+    if !report.errors.is_empty() {
+        let errors = PortsUploadErrors {
+            errors: report.errors,
+        };
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(errors)).into_response();
+    }
+    if report.ports.is_empty() {
+        return FieldErrors::single("body", "no ports listed").into_response();
+    }
+    let out = PortsUpload {
+        count: report.ports.len(),
+        duplicates: report.duplicates,
     };
-    let out = Status {
-        total: s.status.total,
-        scanned,
-        open,
-        state: s.status.state.clone(),
+    app.inner.write().await.uploaded_ports = Some(report.ports);
+    Json(out).into_response()
+}
+
+/// The completed scan before the latest, whole, with its id in `X-Scan-Id`; 204 if there is none.
+async fn get_previous_results(State(app): State<AppState>) -> impl IntoResponse {
+    let s = app.inner.read().await;
+    let Some((id, res)) = s.previous.as_ref() else {
+        return StatusCode::NO_CONTENT.into_response();
     };
-    (StatusCode::OK, Json(out))
+    (
+        StatusCode::OK,
+        [(SCAN_ID_HEADER, id.to_string())],
+        Json(res),
+    )
+        .into_response()
+}
+
+/// What this server's scan API offers, so a UI can build its controls from it.
+#[derive(Debug, Serialize)]
+pub struct Capabilities {
+    pub version: &'static str,
+    /// `ScanResults.version` this server produces.
+    pub schema_version: u32,
+    /// Always-on probes (skipped with `"banners": false`) and the opt-in ones `"probe"` selects.
+    pub probes: ProbeKinds,
+    pub default_ports: Vec<u16>,
+    pub quick_ports: Vec<u16>,
+    pub defaults: RequestDefaults,
+    pub limits: RequestLimits,
+    /// Scanner features and whether `POST /api/scan` can use them; CLI-only ones are `false`.
+    pub features: Features,
 }
 
-async fn get_results(State(app): State<AppState>) -> impl IntoResponse {
+#[derive(Debug, Serialize)]
+pub struct ProbeKinds {
+    pub builtin: &'static [&'static str],
+    pub optional: &'static [&'static str],
+}
+
+#[derive(Debug, Serialize)]
+pub struct RequestDefaults {
+    pub concurrency: usize,
+    pub timeout_ms: u64,
+    /// Timeout ceiling when `quick` is set.
+    pub quick_timeout_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RequestLimits {
+    pub max_concurrency: usize,
+    pub max_timeout_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Features {
+    /// TLS handshake and certificate summary on open ports.
+    pub tls: bool,
+    /// Certificate chain validation (`--tls-verify`).
+    pub tls_verify: bool,
+    /// UDP service sweeps (`--snmp-communities`).
+    pub udp: bool,
+    /// `"dry_run"` scan plans.
+    pub dry_run: bool,
+}
+
+impl Capabilities {
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            schema_version: SCHEMA_VERSION,
+            probes: ProbeKinds {
+                builtin: scanner::BUILTIN_PROBES,
+                optional: scanner::OPTIONAL_PROBES,
+            },
+            default_ports: ports::default_ports(),
+            quick_ports: ports::quick_ports(),
+            defaults: RequestDefaults {
+                concurrency: DEFAULT_REQUEST_CONCURRENCY,
+                timeout_ms: DEFAULT_REQUEST_TIMEOUT_MS,
+                quick_timeout_ms: QUICK_REQUEST_TIMEOUT_MS,
+            },
+            limits: RequestLimits {
+                max_concurrency: MAX_REQUEST_CONCURRENCY,
+                max_timeout_ms: MAX_REQUEST_TIMEOUT_MS,
+            },
+            features: Features {
+                tls: true,
+                tls_verify: false,
+                udp: false,
+                dry_run: true,
+            },
+        }
+    }
+}
+
+async fn get_capabilities() -> Json<Capabilities> {
+    Json(Capabilities::current())
+}
+
+/// Totals and derived metrics of the last scan, for dashboards that don't need the entries.
+#[derive(Debug, Serialize)]
+struct Summary {
+    scanned_total: u64,
+    scanned_done: u64,
+    open_count: u64,
+    closed_count: u64,
+    filtered_count: u64,
+    /// Distinct hosts with at least one open port.
+    hosts_up: usize,
+    /// Distinct hosts that answered on any port, open or refused.
+    live_hosts: usize,
+    /// `open_count / scanned_done`; omitted before anything was scanned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    open_rate: Option<f64>,
+    /// Throughput and connect/probe time; omitted for results loaded without it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timing: Option<ScanTiming>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    connects_per_sec: Option<f64>,
+    /// Mean latency of the open ports; omitted when none were open.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_open_latency_ms: Option<f64>,
+}
+
+async fn get_summary(
+    State(app): State<AppState>,
+    Query(q): Query<ScanIdQuery>,
+) -> impl IntoResponse {
     let s = app.inner.read().await;
-    if let Some(res) = s.results.as_ref() {
-        (StatusCode::OK, Json(res.clone())).into_response()
-    } else {
-        StatusCode::NO_CONTENT.into_response()
+    if let Some(resp) = check_scan_id(&s, &q) {
+        return resp;
     }
+    let Some(res) = s.results.as_ref() else {
+        return StatusCode::NO_CONTENT.into_response();
+    };
+    Json(Summary {
+        scanned_total: res.scanned_total,
+        scanned_done: res.scanned_done,
+        open_count: res.open_count,
+        closed_count: res.closed_count,
+        filtered_count: res.filtered_count,
+        hosts_up: res.hosts_up(),
+        live_hosts: res.live_hosts.len(),
+        open_rate: res.open_rate(),
+        timing: res.timing,
+        connects_per_sec: res.timing.and_then(|t| t.connects_per_sec()),
+        avg_open_latency_ms: res.avg_open_latency_ms(),
+    })
+    .into_response()
+}
+
+async fn get_results_csv(
+    State(app): State<AppState>,
+    Query(f): Query<ResultFilter>,
+) -> impl IntoResponse {
+    download(&app, &f, "text/csv; charset=utf-8", "csv", export::to_csv).await
+}
+
+async fn get_results_xml(
+    State(app): State<AppState>,
+    Query(f): Query<ResultFilter>,
+) -> impl IntoResponse {
+    download(
+        &app,
+        &f,
+        "application/xml; charset=utf-8",
+        "xml",
+        export::to_xml,
+    )
+    .await
+}
+
+/// The last results (filtered by `f`) rendered by `render`, as an attachment named
+/// `lan-scan-results.<ext>`.
+async fn download(
+    app: &AppState,
+    f: &ResultFilter,
+    content_type: &'static str,
+    ext: &str,
+    render: fn(&ScanResults) -> String,
+) -> axum::response::Response {
+    let s = app.inner.read().await;
+    let Some(res) = s.results.as_ref() else {
+        return StatusCode::NO_CONTENT.into_response();
+    };
+    let disposition = format!("attachment; filename=\"lan-scan-results.{ext}\"");
+    (
+        StatusCode::OK,
+        [
+            (CONTENT_TYPE, content_type.to_string()),
+            (CONTENT_DISPOSITION, disposition),
+        ],
+        render(&filter::filter(res, f)),
+    )
+        .into_response()
 }
 
 async fn post_cancel(State(app): State<AppState>) -> impl IntoResponse {
@@ -139,45 +679,105 @@ async fn post_cancel(State(app): State<AppState>) -> impl IntoResponse {
         c.cancel();
     }
     // Return current snapshot; background task will mark done when finished
-    let (scanned, open) = if let Some(p) = s.progress.as_ref() {
-        (
-            p.scanned_done.load(std::sync::atomic::Ordering::Relaxed),
-            p.open_count.load(std::sync::atomic::Ordering::Relaxed),
-        )
+    let out = live_status(&s);
+    (StatusCode::ACCEPTED, Json(out)).into_response()
+}
+
+async fn post_pause(State(app): State<AppState>) -> impl IntoResponse {
+    set_paused(&app, true).await
+}
+
+async fn post_resume(State(app): State<AppState>) -> impl IntoResponse {
+    set_paused(&app, false).await
+}
+
+/// Pause or resume the running scan; 409 if there's no scan in the matching state.
+async fn set_paused(app: &AppState, pause: bool) -> axum::response::Response {
+    let mut s = app.inner.write().await;
+    let (from, to) = if pause {
+        ("running", "paused")
     } else {
-        (s.status.scanned, s.status.open)
+        ("paused", "running")
     };
-    let out = Status {
-        total: s.status.total,
-        scanned,
-        open,
-        state: s.status.state.clone(),
+    let Some(p) = s.progress.clone() else {
+        return (StatusCode::CONFLICT, "no scan in progress").into_response();
     };
-    (StatusCode::ACCEPTED, Json(out)).into_response()
+    if s.status.state != from {
+        return (StatusCode::CONFLICT, format!("scan is {}", s.status.state)).into_response();
+    }
+    if pause {
+        p.pause.pause();
+    } else {
+        p.pause.resume();
+        // Rate samples spanning the pause would understate the real rate.
+        p.reset_rate();
+    }
+    s.status.state = to.into();
+    (StatusCode::OK, Json(live_status(&s))).into_response()
 }
 
-async fn post_scan(State(app): State<AppState>, Json(req): Json<ScanRequest>) -> impl IntoResponse {
-    // Parse targets into IPs (support CIDR strings or plain IPs)
-    let mut all_ips: Vec<IpAddr> = Vec::new();
-    for t in req.targets {
-        if t.contains('/') {
-            match t.parse::<IpNet>() {
-                Ok(n) => all_ips.extend(netdetect::expand_cidr_to_ips(n)),
-                Err(e) => {
-                    return (StatusCode::BAD_REQUEST, format!("invalid CIDR: {e}")).into_response()
-                }
-            }
-        } else {
-            match t.parse::<IpAddr>() {
-                Ok(ip) => all_ips.push(ip),
-                Err(e) => {
-                    return (StatusCode::BAD_REQUEST, format!("invalid IP: {e}")).into_response()
-                }
-            }
+/// The stored status with counters read from the running scan, if any; no ETA.
+fn live_status(s: &ServerState) -> Status {
+    let mut out = Status {
+        eta_seconds: None,
+        ..s.status.clone()
+    };
+    if let Some(p) = s.progress.as_ref() {
+        out.scanned = p.scanned_done.load(std::sync::atomic::Ordering::Relaxed);
+        out.open = p.open_count.load(std::sync::atomic::Ordering::Relaxed);
+        if let Some(total) = p.total() {
+            out.total = total;
+        } else if s.status.total == 0 {
+            // Nothing planned up front: report what the stream has produced so far.
+            out.total = p.scanned_total.load(std::sync::atomic::Ordering::Relaxed);
+            out.indeterminate = true;
         }
     }
+    out
+}
 
-    let mut ports = if req.quick.unwrap_or(false) && req.ports.is_empty() {
+async fn post_scan(
+    State(app): State<AppState>,
+    body: std::result::Result<Json<Value>, JsonRejection>,
+) -> impl IntoResponse {
+    // Unparseable JSON keeps axum's status; well-formed bodies are checked field by field.
+    let body = match body {
+        Ok(Json(body)) => body,
+        Err(rejection) => {
+            let errors = FieldErrors::single("body", rejection.body_text());
+            return (rejection.status(), Json(errors)).into_response();
+        }
+    };
+    let req = match validate_scan_request(body) {
+        Ok(req) => req,
+        Err(errors) => return errors.into_response(),
+    };
+    // Parse targets into IPs (support CIDR strings or plain IPs)
+    let target_spec = req.targets.join(",");
+    let min_prefix_len = if req.force.unwrap_or(false) {
+        None
+    } else {
+        Some(netdetect::DEFAULT_MIN_PREFIX_LEN)
+    };
+    let all_ips = match netdetect::target_set(&req.targets, min_prefix_len) {
+        Ok(set) => set,
+        Err(e) => return FieldErrors::single("targets", format!("{e:#}")).into_response(),
+    };
+
+    let uploaded = if req.uploaded_ports == Some(true) {
+        match app.inner.read().await.uploaded_ports.clone() {
+            Some(list) => Some(list),
+            None => {
+                let msg = "no port list uploaded (POST /api/ports first)";
+                return FieldErrors::single("uploaded_ports", msg).into_response();
+            }
+        }
+    } else {
+        None
+    };
+    let mut ports = if let Some(list) = uploaded {
+        list
+    } else if req.quick.unwrap_or(false) && req.ports.is_empty() {
         ports::quick_ports()
     } else if req.ports.is_empty() {
         ports::default_ports()
@@ -188,11 +788,33 @@ async fn post_scan(State(app): State<AppState>, Json(req): Json<ScanRequest>) ->
         ports.retain(|p| !ex.contains(p));
     }
 
+    if req.dry_run.unwrap_or(false) {
+        return (
+            StatusCode::OK,
+            Json(ScanPlan::for_distinct(all_ips.hosts(), &ports)),
+        )
+            .into_response();
+    }
+
     let total = (all_ips.len() as u64) * (ports.len() as u64);
-    let concurrency = req.concurrency.unwrap_or(1000);
-    let mut timeout = Duration::from_millis(req.timeout_ms.unwrap_or(400));
-    if req.quick.unwrap_or(false) && timeout > Duration::from_millis(250) {
-        timeout = Duration::from_millis(250);
+    let concurrency = req.concurrency.unwrap_or(DEFAULT_REQUEST_CONCURRENCY);
+    let mut timeout_ms = req.timeout_ms.unwrap_or(DEFAULT_REQUEST_TIMEOUT_MS);
+    if req.quick.unwrap_or(false) {
+        timeout_ms = timeout_ms.min(QUICK_REQUEST_TIMEOUT_MS);
+    }
+    let timeout = Duration::from_millis(timeout_ms);
+    let mut options = ScanOptions {
+        concurrency,
+        timeout,
+        probe_redis: req.probe_redis.unwrap_or(false),
+        grab_banners: req.banners.unwrap_or(true),
+        max_duration: req.max_duration_ms.map(Duration::from_millis),
+        ..ScanOptions::default()
+    };
+    for name in req.probe.iter().flatten() {
+        if let Err(e) = options.enable_probe(name) {
+            return FieldErrors::single("probe", format!("{e:#}")).into_response();
+        }
     }
 
     // Prepare shared progress and cancel token
@@ -200,43 +822,50 @@ async fn post_scan(State(app): State<AppState>, Json(req): Json<ScanRequest>) ->
     let cancel = CancellationToken::new();
 
     // Update state
-    {
-        let mut s = app.inner.write().await;
-        // Cancel any existing scan
-        if let Some(c) = s.cancel.take() {
-            c.cancel();
-        }
-        s.status = Status {
-            total,
-            scanned: 0,
-            open: 0,
-            state: "running".into(),
-        };
-        s.results = None;
-        s.progress = Some(progress.clone());
-        s.cancel = Some(cancel.clone());
-    }
+    let id = app
+        .inner
+        .write()
+        .await
+        .begin_scan(total, progress.clone(), cancel.clone());
 
     // Spawn scan task
     let app2 = app.clone();
+    let stamps = options.timestamps();
+    let mut meta = ScanMeta::begin(&target_spec, concurrency, timeout, ports.len(), stamps);
     tokio::spawn(async move {
-        let res = scanner::scan_targets_with_shared_opts(
-            &all_ips,
+        let started = Instant::now();
+        let res = scanner::scan_stream_shared(
+            tokio_stream::iter(all_ips.hosts()),
             &ports,
-            concurrency,
-            timeout,
+            options,
             cancel.clone(),
             progress.clone(),
-            req.probe_redis.unwrap_or(false),
         )
         .await;
 
         let mut s = app2.inner.write().await;
+        // A newer scan replaced this one; its state is no longer ours to update.
+        if s.status.id != Some(id) {
+            return;
+        }
         match res {
-            Ok(results) => {
+            Ok(mut results) => {
+                meta.finish(started.elapsed(), stamps);
+                results.meta = Some(meta);
                 s.status.scanned = results.scanned_done;
                 s.status.open = results.open_count;
                 s.status.state = "done".into();
+                if let Some(hook) = app2.config.webhook.clone() {
+                    if let Some(body) = alert::render(hook.format, &results, None) {
+                        tokio::spawn(async move {
+                            if let Err(e) = webhook::post_json(&hook, &body).await {
+                                eprintln!("webhook failed: {e:#}");
+                            }
+                        });
+                    }
+                }
+                // Sorted once here so paged reads see a fixed order.
+                results.sort_entries();
                 s.results = Some(results);
                 s.progress = None;
                 s.cancel = None;
@@ -253,11 +882,117 @@ async fn post_scan(State(app): State<AppState>, Json(req): Json<ScanRequest>) ->
     (
         StatusCode::ACCEPTED,
         Json(Status {
+            id: Some(id),
             total,
             scanned: 0,
             open: 0,
             state: "running".into(),
+            eta_seconds: None,
+            indeterminate: false,
         }),
     )
         .into_response()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn errors(body: Value) -> BTreeMap<String, String> {
+        validate_scan_request(body).unwrap_err().errors
+    }
+
+    #[test]
+    fn scan_request_errors_name_each_bad_field() {
+        let errs = errors(json!({ "concurrency": -5, "timeout_ms": 120000, "color": "red" }));
+        assert_eq!(errs["targets"], "required");
+        assert!(errs["concurrency"].contains("expected usize"), "{errs:?}");
+        assert_eq!(errs["color"], "unknown field");
+        // Type errors are reported first; range checks run once every field parses.
+        assert!(!errs.contains_key("timeout_ms"));
+
+        let errs = errors(json!({ "targets": [], "timeout_ms": 120000, "ports": [0, 22] }));
+        assert_eq!(errs["targets"], "must not be empty");
+        assert_eq!(errs["timeout_ms"], "must be <= 60000");
+        assert_eq!(errs["ports"], "port 0 is not valid");
+
+        assert_eq!(errors(json!(["10.0.0.1"]))["body"], "must be a JSON object");
+        let errs =
+            errors(json!({ "targets": ["10.0.0.1"], "ports": [22], "uploaded_ports": true }));
+        assert_eq!(errs["uploaded_ports"], "cannot be combined with ports");
+        let errs = errors(json!({ "targets": ["10.0.0.1"], "max_duration_ms": 0 }));
+        assert_eq!(errs["max_duration_ms"], "must be >= 1");
+        let req = validate_scan_request(json!({ "targets": ["10.0.0.0/30"], "ports": [22] }));
+        assert_eq!(req.unwrap().ports, [22]);
+    }
+
+    #[tokio::test]
+    async fn built_in_ui_is_served_without_a_ui_dir() {
+        use tower::ServiceExt;
+        let get_page = |path: &str| {
+            let app = with_ui(
+                Router::new(),
+                Path::new(DEFAULT_UI_DIR).join("missing").as_path(),
+            );
+            let req = axum::http::Request::get(path)
+                .body(axum::body::Body::empty())
+                .unwrap();
+            async move {
+                let resp = app.oneshot(req).await.unwrap();
+                let ct = resp.headers()[CONTENT_TYPE].to_str().unwrap().to_string();
+                let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (ct, String::from_utf8(body.to_vec()).unwrap())
+            }
+        };
+        let (ct, body) = get_page("/app.js?v=2").await;
+        assert!(ct.starts_with("text/javascript"));
+        assert_eq!(body, EMBEDDED_APP_JS);
+        let (ct, body) = get_page("/scans/7").await;
+        assert!(ct.starts_with("text/html"));
+        assert!(body.contains("/app.js") && body.contains("/style.css"));
+    }
+
+    #[test]
+    fn capabilities_describe_request_defaults_and_probes() {
+        let caps = serde_json::to_value(Capabilities::current()).unwrap();
+        assert_eq!(caps["defaults"]["timeout_ms"], 400);
+        assert_eq!(caps["limits"]["max_timeout_ms"], MAX_REQUEST_TIMEOUT_MS);
+        assert_eq!(caps["probes"]["optional"][0], "redis");
+        assert_eq!(caps["features"]["udp"], false);
+        assert!(!caps["quick_ports"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn new_scan_keeps_the_last_completed_results_as_previous() {
+        let mut s = ServerState {
+            status: Status::default(),
+            results: None,
+            previous: None,
+            uploaded_ports: None,
+            progress: None,
+            cancel: None,
+            last_id: 0,
+        };
+        let first = s.begin_scan(4, SharedProgress::new(), CancellationToken::new());
+        s.results = Some(ScanResults {
+            open_count: 1,
+            ..ScanResults::default()
+        });
+        let old_cancel = CancellationToken::new();
+        s.cancel = Some(old_cancel.clone());
+        let second = s.begin_scan(4, SharedProgress::new(), CancellationToken::new());
+        assert!(old_cancel.is_cancelled());
+        assert_eq!((first, second), (1, 2));
+        assert!(s.results.is_none());
+        assert_eq!(
+            s.previous.as_ref().map(|(id, r)| (*id, r.open_count)),
+            Some((1, 1))
+        );
+        // A scan that never completed leaves the older report in place.
+        s.begin_scan(4, SharedProgress::new(), CancellationToken::new());
+        assert_eq!(s.previous.as_ref().map(|(id, _)| *id), Some(1));
+    }
+}
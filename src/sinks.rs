@@ -0,0 +1,280 @@
+//! Where a scan's output goes. `main` builds one sink per output flag; each sink sees every finding
+//! as it is recorded and the finished results once, so any mix of outputs (table to stdout and
+//! JSON to a file, syslog plus a webhook, ...) runs side by side.
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use lan_scan_rs::diff::{self, Baseline};
+use lan_scan_rs::scanner::EntryCallback;
+use lan_scan_rs::syslog::{SyslogSender, SyslogTarget};
+use lan_scan_rs::types::{ScanEntry, ScanResults, Timestamps};
+use lan_scan_rs::webhook::{self, WebhookConfig};
+use lan_scan_rs::{alert, export};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::TableView;
+
+/// One destination for scan output. Failures are reported by the sink itself (a warning on
+/// stderr) and never stop the others.
+pub trait OutputSink: Send {
+    /// An open port as soon as the scan records it. Called from the scan's connect tasks, so keep
+    /// it quick and hand slow work to a task.
+    fn on_entry(&mut self, _entry: &ScanEntry) {}
+
+    /// The finished results, after retries, SNMP, and baseline annotations.
+    fn write_results(&mut self, results: &ScanResults);
+
+    /// Work still in flight after `write_results` (a delivery), awaited before the run ends.
+    fn take_task(&mut self) -> Option<JoinHandle<()>> {
+        None
+    }
+}
+
+pub type Sinks = Arc<Mutex<Vec<Box<dyn OutputSink>>>>;
+
+/// Scan callback that hands each recorded entry to every sink's `on_entry`.
+pub fn entry_callback(sinks: &Sinks) -> EntryCallback {
+    let sinks = Arc::clone(sinks);
+    EntryCallback::new(move |entry| {
+        for sink in sinks.lock().expect("output sinks poisoned").iter_mut() {
+            sink.on_entry(entry);
+        }
+    })
+}
+
+/// Run every sink over the finished results, in order, then wait for their deliveries.
+pub async fn finish(sinks: &Sinks, results: &ScanResults) {
+    let mut sinks = std::mem::take(&mut *sinks.lock().expect("output sinks poisoned"));
+    for sink in &mut sinks {
+        sink.write_results(results);
+    }
+    for task in sinks.iter_mut().filter_map(|sink| sink.take_task()) {
+        let _ = task.await;
+    }
+}
+
+/// The results table on stdout, with the SMB relay and unauthenticated service call-outs and the
+/// stats footer.
+pub struct TableSink {
+    pub view: TableView,
+}
+
+impl OutputSink for TableSink {
+    fn write_results(&mut self, results: &ScanResults) {
+        crate::print_results_table(results, self.view);
+        crate::print_smb_relay_hosts(results, self.view.palette);
+        crate::print_unauthenticated_services(results, self.view.palette);
+        crate::print_stats_footer(results);
+    }
+}
+
+/// `--output`: pretty JSON to a file, or to stdout for `-`.
+pub struct JsonSink {
+    pub path: PathBuf,
+}
+
+impl OutputSink for JsonSink {
+    fn write_results(&mut self, results: &ScanResults) {
+        if let Err(e) = write_results_json(&self.path, results) {
+            eprintln!("Failed to write JSON to {}: {}", self.path.display(), e);
+        } else {
+            status!("Wrote JSON results to {}", self.path.display());
+        }
+    }
+}
+
+fn write_results_json(path: &Path, results: &ScanResults) -> anyhow::Result<()> {
+    if crate::output_is_stdout(Some(path)) {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, results)?;
+        writeln!(out)?;
+        return Ok(());
+    }
+    let file = File::create(path)?;
+    serde_json::to_writer_pretty(file, results)?;
+    Ok(())
+}
+
+/// `--save-baseline`: the open set, for later `--baseline` runs.
+pub struct BaselineSink {
+    pub path: PathBuf,
+    pub stamps: Timestamps,
+}
+
+impl OutputSink for BaselineSink {
+    fn write_results(&mut self, results: &ScanResults) {
+        if results.cancelled {
+            eprintln!(
+                "Warning: the scan did not finish; the baseline only holds the ports found before it stopped"
+            );
+        }
+        let baseline = Baseline::from_results(results, self.stamps);
+        match diff::save_baseline(&self.path, &baseline) {
+            Ok(()) => status!(
+                "Saved baseline ({} open port(s)) to {}",
+                baseline.ports.len(),
+                self.path.display()
+            ),
+            Err(e) => eprintln!("Failed to save baseline: {e:#}"),
+        }
+    }
+}
+
+/// `--ports-summary`: the nmap `-p` list on stderr (`-`), or in a file.
+pub struct PortsSummarySink {
+    pub path: PathBuf,
+    pub per_host: bool,
+}
+
+impl OutputSink for PortsSummarySink {
+    fn write_results(&mut self, results: &ScanResults) {
+        let text = export::to_port_list(results, self.per_host);
+        if self.path == Path::new("-") {
+            eprint!("{text}");
+        } else if let Err(e) = std::fs::write(&self.path, text) {
+            eprintln!(
+                "Failed to write ports summary to {}: {}",
+                self.path.display(),
+                e
+            );
+        } else {
+            status!("Wrote ports summary to {}", self.path.display());
+        }
+    }
+}
+
+/// `--syslog` / `--syslog-local`: each open port as it is found. Messages go out from a task, so
+/// a slow collector never holds up the scan.
+pub struct SyslogSink {
+    tx: Option<mpsc::UnboundedSender<ScanEntry>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl SyslogSink {
+    /// `None` (after a warning) when the endpoint can't be reached: the scan goes on without it.
+    pub async fn connect(target: &SyslogTarget) -> Option<Self> {
+        let sender = match SyslogSender::connect(target).await {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Warning: syslog unavailable ({e}); continuing without it");
+                return None;
+            }
+        };
+        let (tx, mut rx) = mpsc::unbounded_channel::<ScanEntry>();
+        let task = tokio::spawn(async move {
+            let mut warned = false;
+            while let Some(entry) = rx.recv().await {
+                if let Err(e) = sender.send_entry(&entry).await {
+                    if !warned {
+                        eprintln!("Warning: failed to send syslog message: {e}");
+                        warned = true;
+                    }
+                }
+            }
+        });
+        Some(Self {
+            tx: Some(tx),
+            task: Some(task),
+        })
+    }
+}
+
+impl OutputSink for SyslogSink {
+    fn on_entry(&mut self, entry: &ScanEntry) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(entry.clone());
+        }
+    }
+
+    fn write_results(&mut self, _results: &ScanResults) {
+        // Closing the channel lets the sender task drain what's queued and exit.
+        self.tx = None;
+    }
+
+    fn take_task(&mut self) -> Option<JoinHandle<()>> {
+        self.task.take()
+    }
+}
+
+/// `--webhook`: the completion summary, listing new findings when a baseline was given.
+pub struct WebhookSink {
+    pub hook: WebhookConfig,
+    pub baseline: Option<Baseline>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl WebhookSink {
+    pub fn new(hook: WebhookConfig, baseline: Option<Baseline>) -> Self {
+        Self {
+            hook,
+            baseline,
+            task: None,
+        }
+    }
+}
+
+impl OutputSink for WebhookSink {
+    fn write_results(&mut self, results: &ScanResults) {
+        let new_findings = self
+            .baseline
+            .as_ref()
+            .map(|baseline| diff::new_open_entries(baseline, results));
+        let Some(body) = alert::render(self.hook.format, results, new_findings.as_deref()) else {
+            status!("Webhook skipped: nothing to report");
+            return;
+        };
+        let hook = self.hook.clone();
+        // Failures only warn; the scan itself already succeeded.
+        self.task = Some(tokio::spawn(async move {
+            match webhook::post_json(&hook, &body).await {
+                Ok(status) => status!("Webhook delivered (HTTP {status})"),
+                Err(e) => eprintln!("Warning: webhook failed: {e:#}"),
+            }
+        }));
+    }
+
+    fn take_task(&mut self) -> Option<JoinHandle<()>> {
+        self.task.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts what it was handed.
+    struct Counting(Arc<Mutex<(usize, usize)>>);
+
+    impl OutputSink for Counting {
+        fn on_entry(&mut self, _entry: &ScanEntry) {
+            self.0.lock().unwrap().0 += 1;
+        }
+
+        fn write_results(&mut self, _results: &ScanResults) {
+            self.0.lock().unwrap().1 += 1;
+        }
+    }
+
+    #[tokio::test]
+    async fn every_sink_sees_entries_and_results() {
+        let seen: Vec<Arc<Mutex<(usize, usize)>>> = (0..2).map(|_| Arc::default()).collect();
+        let sinks: Sinks = Arc::new(Mutex::new(
+            seen.iter()
+                .map(|s| Box::new(Counting(s.clone())) as Box<dyn OutputSink>)
+                .collect(),
+        ));
+        let callback = entry_callback(&sinks);
+        let entry: ScanEntry =
+            serde_json::from_str(r#"{"ip":"10.0.0.1","port":22,"open":true,"latency_ms":1,"service":null,"banner":null,"timestamp":""}"#)
+                .unwrap();
+        callback.call(&entry);
+        callback.call(&entry);
+        finish(&sinks, &ScanResults::default()).await;
+        for s in &seen {
+            assert_eq!(*s.lock().unwrap(), (2, 1));
+        }
+    }
+}
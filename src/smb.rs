@@ -0,0 +1,278 @@
+//! SMB fingerprinting for ports 139/445: a NetBIOS node status query (UDP 137) for the computer
+//! name and workgroup, and an optional SMB2 NEGOTIATE for the dialect and signing policy.
+use crate::dns;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::time;
+
+pub const NETBIOS_NS_PORT: u16 = 137;
+/// NBSTAT (node status) query type.
+const TYPE_NBSTAT: u16 = 0x21;
+/// `*` padded to 16 bytes, first-level encoded: the wildcard name every node answers for.
+const WILDCARD_NAME: &str = "CKAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
+const NAME_QUERY_TIMEOUT: Duration = Duration::from_millis(500);
+const NEGOTIATE_TIMEOUT: Duration = Duration::from_millis(800);
+
+/// Dialects offered in the NEGOTIATE, oldest first.
+const DIALECTS: [u16; 5] = [0x0202, 0x0210, 0x0300, 0x0302, 0x0311];
+
+/// What the SMB probes learned about a host.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SmbInfo {
+    pub computer: Option<String>,
+    pub domain: Option<String>,
+    /// Negotiated dialect, e.g. `3.1.1`.
+    pub dialect: Option<String>,
+    pub signing: Option<Signing>,
+}
+
+/// Server signing policy from the NEGOTIATE response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signing {
+    Required,
+    /// Supported but not required: sessions can still be relayed.
+    Enabled,
+    Disabled,
+}
+
+impl Signing {
+    fn as_str(self) -> &'static str {
+        match self {
+            Signing::Required => "required",
+            Signing::Enabled => "enabled",
+            Signing::Disabled => "disabled",
+        }
+    }
+}
+
+impl SmbInfo {
+    /// Whether NTLM relay is possible, i.e. the server doesn't insist on signing.
+    pub fn relay_risk(&self) -> bool {
+        matches!(self.signing, Some(Signing::Enabled | Signing::Disabled))
+    }
+
+    /// Banner text: `smb computer=FILESRV01 domain=WORKGROUP signing=disabled dialect=3.1.1`.
+    pub fn banner(&self) -> Option<String> {
+        let mut out = String::from("smb");
+        if let Some(c) = &self.computer {
+            out.push_str(&format!(" computer={c}"));
+        }
+        if let Some(d) = &self.domain {
+            out.push_str(&format!(" domain={d}"));
+        }
+        if let Some(s) = self.signing {
+            out.push_str(&format!(" signing={}", s.as_str()));
+        }
+        if let Some(d) = &self.dialect {
+            out.push_str(&format!(" dialect={d}"));
+        }
+        (out.len() > 3).then_some(out)
+    }
+}
+
+/// Whether a scan banner produced by [`SmbInfo::banner`] reports signing as not required.
+pub fn banner_has_relay_risk(banner: &str) -> bool {
+    banner.starts_with("smb ")
+        && banner
+            .split(' ')
+            .any(|kv| kv == "signing=enabled" || kv == "signing=disabled")
+}
+
+/// Fingerprint an open SMB port. The NetBIOS name query always runs; the NEGOTIATE is only sent
+/// on 445 (direct SMB) when `negotiate` is set.
+pub async fn probe(stream: &mut TcpStream, ip: IpAddr, port: u16, negotiate: bool) -> SmbInfo {
+    let mut info = SmbInfo::default();
+    if let IpAddr::V4(v4) = ip {
+        if let Some((computer, domain)) = name_query(v4).await {
+            info.computer = computer;
+            info.domain = domain;
+        }
+    }
+    if negotiate && port == 445 {
+        if let Some((dialect, signing)) = smb2_negotiate(stream).await {
+            info.dialect = Some(dialect);
+            info.signing = Some(signing);
+        }
+    }
+    info
+}
+
+/// Ask the host's NetBIOS name service for its name table; returns (computer, workgroup/domain).
+async fn name_query(ip: Ipv4Addr) -> Option<(Option<String>, Option<String>)> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await.ok()?;
+    let id = std::process::id() as u16;
+    let query = dns::build_query(id, &[(WILDCARD_NAME, TYPE_NBSTAT)], dns::CLASS_IN);
+    socket.send_to(&query, (ip, NETBIOS_NS_PORT)).await.ok()?;
+    let mut buf = [0u8; 1024];
+    let (n, _) = time::timeout(NAME_QUERY_TIMEOUT, socket.recv_from(&mut buf))
+        .await
+        .ok()?
+        .ok()?;
+    let msg = dns::parse_message(&buf[..n]).ok()?;
+    if msg.id != id {
+        return None;
+    }
+    msg.records.iter().find_map(|r| match &r.data {
+        dns::RecordData::Other(rdata) if r.rtype == TYPE_NBSTAT => Some(parse_name_table(rdata)),
+        _ => None,
+    })
+}
+
+/// Pick the computer name (unique `<00>`) and workgroup (group `<00>`) out of a node status
+/// name table.
+fn parse_name_table(rdata: &[u8]) -> (Option<String>, Option<String>) {
+    let Some((&count, mut rest)) = rdata.split_first() else {
+        return (None, None);
+    };
+    let (mut computer, mut domain) = (None, None);
+    for _ in 0..count {
+        if rest.len() < 18 {
+            break;
+        }
+        let name = String::from_utf8_lossy(&rest[..15]).trim_end().to_string();
+        let suffix = rest[15];
+        let group = rest[16] & 0x80 != 0;
+        if suffix == 0x00 && !name.is_empty() {
+            let slot = if group { &mut domain } else { &mut computer };
+            slot.get_or_insert(name);
+        }
+        rest = &rest[18..];
+    }
+    (computer, domain)
+}
+
+/// Send an SMB2 NEGOTIATE and read back the chosen dialect and signing policy.
+async fn smb2_negotiate(stream: &mut TcpStream) -> Option<(String, Signing)> {
+    time::timeout(NEGOTIATE_TIMEOUT, stream.write_all(&negotiate_request()))
+        .await
+        .ok()?
+        .ok()?;
+    let mut buf = vec![0u8; 1024];
+    let mut len = 0;
+    // NetBIOS session header (4) + SMB2 header (64) + the first 6 bytes of the response body.
+    while len < 4 + 64 + 6 {
+        let n = time::timeout(NEGOTIATE_TIMEOUT, stream.read(&mut buf[len..]))
+            .await
+            .ok()?
+            .ok()?;
+        if n == 0 {
+            return None;
+        }
+        len += n;
+    }
+    parse_negotiate_response(&buf[..len])
+}
+
+fn negotiate_request() -> Vec<u8> {
+    let mut smb = Vec::with_capacity(192);
+    // SMB2 header
+    smb.extend_from_slice(b"\xfeSMB");
+    smb.extend_from_slice(&64u16.to_le_bytes()); // structure size
+    smb.extend_from_slice(&[0; 6]); // credit charge, status
+    smb.extend_from_slice(&0u16.to_le_bytes()); // command: NEGOTIATE
+    smb.extend_from_slice(&1u16.to_le_bytes()); // credits requested
+    smb.extend_from_slice(&[0; 48]); // flags .. signature
+
+    // NEGOTIATE request
+    let dialects_end = 64 + 36 + DIALECTS.len() * 2;
+    let context_offset = (dialects_end + 7) & !7;
+    smb.extend_from_slice(&36u16.to_le_bytes());
+    smb.extend_from_slice(&(DIALECTS.len() as u16).to_le_bytes());
+    smb.extend_from_slice(&1u16.to_le_bytes()); // security mode: signing enabled
+    smb.extend_from_slice(&[0; 2]);
+    smb.extend_from_slice(&0u32.to_le_bytes()); // capabilities
+    smb.extend_from_slice(b"lan-scan-rs\0\0\0\0\0"); // client GUID
+    smb.extend_from_slice(&(context_offset as u32).to_le_bytes());
+    smb.extend_from_slice(&1u16.to_le_bytes()); // one negotiate context
+    smb.extend_from_slice(&[0; 2]);
+    for d in DIALECTS {
+        smb.extend_from_slice(&d.to_le_bytes());
+    }
+    smb.resize(context_offset, 0);
+    // SMB 3.1.1 requires a preauth integrity context (SHA-512, 32-byte salt).
+    smb.extend_from_slice(&1u16.to_le_bytes());
+    smb.extend_from_slice(&38u16.to_le_bytes());
+    smb.extend_from_slice(&[0; 4]);
+    smb.extend_from_slice(&1u16.to_le_bytes());
+    smb.extend_from_slice(&32u16.to_le_bytes());
+    smb.extend_from_slice(&1u16.to_le_bytes());
+    smb.extend_from_slice(&[0x5a; 32]);
+
+    let mut pkt = Vec::with_capacity(smb.len() + 4);
+    pkt.push(0); // NetBIOS session message
+    pkt.extend_from_slice(&(smb.len() as u32).to_be_bytes()[1..]);
+    pkt.extend_from_slice(&smb);
+    pkt
+}
+
+fn parse_negotiate_response(buf: &[u8]) -> Option<(String, Signing)> {
+    let smb = buf.get(4..)?;
+    if !smb.starts_with(b"\xfeSMB") || smb.get(8..12)? != [0, 0, 0, 0] {
+        return None;
+    }
+    let body = smb.get(64..70)?;
+    let security_mode = u16::from_le_bytes([body[2], body[3]]);
+    let dialect = match u16::from_le_bytes([body[4], body[5]]) {
+        0x0202 => "2.0.2".to_string(),
+        0x0210 => "2.1".to_string(),
+        0x0300 => "3.0".to_string(),
+        0x0302 => "3.0.2".to_string(),
+        0x0311 => "3.1.1".to_string(),
+        other => format!("0x{other:04x}"),
+    };
+    let signing = if security_mode & 0x02 != 0 {
+        Signing::Required
+    } else if security_mode & 0x01 != 0 {
+        Signing::Enabled
+    } else {
+        Signing::Disabled
+    };
+    Some((dialect, signing))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name_entry(name: &str, suffix: u8, group: bool) -> Vec<u8> {
+        let mut e = format!("{name:<15}").into_bytes();
+        e.push(suffix);
+        e.extend_from_slice(&[if group { 0x84 } else { 0x04 }, 0x00]);
+        e
+    }
+
+    #[test]
+    fn reads_computer_and_workgroup_from_name_table() {
+        let mut rdata = vec![3];
+        rdata.extend(name_entry("FILESRV01", 0x00, false));
+        rdata.extend(name_entry("FILESRV01", 0x20, false));
+        rdata.extend(name_entry("WORKGROUP", 0x00, true));
+        assert_eq!(
+            parse_name_table(&rdata),
+            (Some("FILESRV01".into()), Some("WORKGROUP".into()))
+        );
+    }
+
+    #[test]
+    fn negotiate_response_yields_dialect_and_signing() {
+        let mut resp = vec![0, 0, 0, 0];
+        resp.extend_from_slice(b"\xfeSMB");
+        resp.resize(4 + 64, 0);
+        resp.extend_from_slice(&[65, 0, 0x01, 0x00, 0x11, 0x03]);
+        let (dialect, signing) = parse_negotiate_response(&resp).unwrap();
+        let info = SmbInfo {
+            computer: Some("FILESRV01".into()),
+            domain: Some("WORKGROUP".into()),
+            dialect: Some(dialect),
+            signing: Some(signing),
+        };
+        assert!(info.relay_risk());
+        assert_eq!(
+            info.banner().unwrap(),
+            "smb computer=FILESRV01 domain=WORKGROUP signing=enabled dialect=3.1.1"
+        );
+        assert_eq!(negotiate_request().len(), 4 + 112 + 8 + 38);
+    }
+}
@@ -0,0 +1,225 @@
+//! SNMP community check: a v2c GetRequest for `sysDescr.0` over UDP 161, one per community, until
+//! one is answered. Only the bits of BER needed for that request and its response are handled.
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time;
+
+pub const SNMP_PORT: u16 = 161;
+/// Community tried when `--snmp-communities` is given without a value.
+pub const DEFAULT_COMMUNITY: &str = "public";
+/// `1.3.6.1.2.1.1.1.0` (sysDescr.0), BER-encoded.
+const SYS_DESCR_OID: [u8; 8] = [0x2b, 6, 1, 2, 1, 1, 1, 0];
+/// sysDescr is free text; keep banners readable.
+const MAX_DESCR_LEN: usize = 160;
+
+/// A community that the agent answered, and the sysDescr it returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnmpInfo {
+    pub community: String,
+    pub sys_descr: String,
+}
+
+impl SnmpInfo {
+    /// Banner text: `snmp community=public sysDescr="Linux nas 5.10.0 ..."`.
+    pub fn banner(&self) -> String {
+        let default = if self.community == DEFAULT_COMMUNITY {
+            " (default community)"
+        } else {
+            ""
+        };
+        format!(
+            "snmp community={}{default} sysDescr=\"{}\"",
+            self.community, self.sys_descr
+        )
+    }
+}
+
+/// Try each community in turn; returns the first one the agent answers.
+pub async fn probe(ip: IpAddr, communities: &[String], timeout: Duration) -> Option<SnmpInfo> {
+    let bind: IpAddr = match ip {
+        IpAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        IpAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    let socket = UdpSocket::bind((bind, 0)).await.ok()?;
+    socket.connect((ip, SNMP_PORT)).await.ok()?;
+    let mut buf = [0u8; 1500];
+    for (i, community) in communities.iter().enumerate() {
+        let request_id = 0x4c53_0000 | i as i32;
+        socket
+            .send(&get_request(community, request_id))
+            .await
+            .ok()?;
+        // Agents stay silent on a wrong community, so a timeout just means "try the next one".
+        if let Ok(Ok(n)) = time::timeout(timeout, socket.recv(&mut buf)).await {
+            if let Some(descr) = parse_response(&buf[..n], request_id) {
+                return Some(SnmpInfo {
+                    community: community.clone(),
+                    sys_descr: descr,
+                });
+            }
+        }
+    }
+    None
+}
+
+/// Encode a v2c GetRequest for sysDescr.0.
+fn get_request(community: &str, request_id: i32) -> Vec<u8> {
+    let varbind = tlv(
+        0x30,
+        &[tlv(0x06, &SYS_DESCR_OID), vec![0x05, 0x00]].concat(),
+    );
+    let pdu = [
+        integer(request_id),
+        vec![0x02, 0x01, 0x00], // error-status
+        vec![0x02, 0x01, 0x00], // error-index
+        tlv(0x30, &varbind),
+    ]
+    .concat();
+    let message = [
+        vec![0x02, 0x01, 0x01], // version: v2c
+        tlv(0x04, community.as_bytes()),
+        tlv(0xa0, &pdu),
+    ]
+    .concat();
+    tlv(0x30, &message)
+}
+
+/// Minimal-length INTEGER (request ids are never negative).
+fn integer(v: i32) -> Vec<u8> {
+    let bytes = v.to_be_bytes();
+    let skip = (0..3)
+        .take_while(|&i| bytes[i] == 0 && bytes[i + 1] < 0x80)
+        .count();
+    tlv(0x02, &bytes[skip..])
+}
+
+fn tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = value.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = (len as u32).to_be_bytes();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count();
+        out.push(0x80 | (4 - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+    out.extend_from_slice(value);
+    out
+}
+
+/// Split one TLV off the front of `buf`: `(tag, value, rest)`.
+fn read_tlv(buf: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = buf.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (len, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let n = (first & 0x7f) as usize;
+        if n == 0 || n > 4 || rest.len() < n {
+            return None;
+        }
+        let len = rest[..n]
+            .iter()
+            .fold(0usize, |acc, &b| (acc << 8) | b as usize);
+        (len, &rest[n..])
+    };
+    if rest.len() < len {
+        return None;
+    }
+    Some((tag, &rest[..len], &rest[len..]))
+}
+
+/// Pull sysDescr out of a GetResponse matching `request_id`; `None` on errors or exceptions.
+fn parse_response(buf: &[u8], request_id: i32) -> Option<String> {
+    let (0x30, message, _) = read_tlv(buf)? else {
+        return None;
+    };
+    let (_, _version, rest) = read_tlv(message)?;
+    let (_, _community, rest) = read_tlv(rest)?;
+    let (0xa2, pdu, _) = read_tlv(rest)? else {
+        return None;
+    };
+    let (_, id, rest) = read_tlv(pdu)?;
+    let id = id.iter().fold(0i64, |acc, &b| (acc << 8) | b as i64);
+    if id != request_id as i64 {
+        return None;
+    }
+    let (_, error_status, rest) = read_tlv(rest)?;
+    if error_status.iter().any(|&b| b != 0) {
+        return None;
+    }
+    let (_, _error_index, rest) = read_tlv(rest)?;
+    let (_, varbinds, _) = read_tlv(rest)?;
+    let (_, varbind, _) = read_tlv(varbinds)?;
+    let (_, _oid, rest) = read_tlv(varbind)?;
+    // noSuchObject & co. come back as context-tagged values rather than an OCTET STRING.
+    let (0x04, value, _) = read_tlv(rest)? else {
+        return None;
+    };
+    let mut descr = String::from_utf8_lossy(value)
+        .trim()
+        .replace('\r', "\\r")
+        .replace('\n', "\\n");
+    if descr.len() > MAX_DESCR_LEN {
+        let mut end = MAX_DESCR_LEN;
+        while !descr.is_char_boundary(end) {
+            end -= 1;
+        }
+        descr.truncate(end);
+    }
+    Some(descr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The agent's GetResponse carrying sysDescr.0.
+    fn response(community: &str, request_id: i32, descr: &str) -> Vec<u8> {
+        let varbind = tlv(
+            0x30,
+            &[tlv(0x06, &SYS_DESCR_OID), tlv(0x04, descr.as_bytes())].concat(),
+        );
+        let pdu = [
+            integer(request_id),
+            vec![0x02, 0x01, 0x00, 0x02, 0x01, 0x00],
+            tlv(0x30, &varbind),
+        ]
+        .concat();
+        let message = [
+            vec![0x02, 0x01, 0x01],
+            tlv(0x04, community.as_bytes()),
+            tlv(0xa2, &pdu),
+        ]
+        .concat();
+        tlv(0x30, &message)
+    }
+
+    #[test]
+    fn encodes_get_request_for_sys_descr() {
+        let req = get_request("public", 1);
+        assert_eq!(
+            req,
+            [
+                0x30, 0x26, 0x02, 0x01, 0x01, 0x04, 0x06, b'p', b'u', b'b', b'l', b'i', b'c', 0xa0,
+                0x19, 0x02, 0x01, 1, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00, 0x30, 0x0e, 0x30, 0x0c,
+                0x06, 0x08, 0x2b, 6, 1, 2, 1, 1, 1, 0, 0x05, 0x00,
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_sys_descr_from_matching_response() {
+        let descr = "Linux nas 5.10.0 #1 SMP x86_64";
+        let resp = response("public", 7, descr);
+        assert_eq!(parse_response(&resp, 7).as_deref(), Some(descr));
+        assert_eq!(parse_response(&resp, 8), None);
+        let long = "x".repeat(300);
+        assert_eq!(
+            parse_response(&response("public", 7, &long), 7).map(|d| d.len()),
+            Some(MAX_DESCR_LEN)
+        );
+    }
+}
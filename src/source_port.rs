@@ -0,0 +1,226 @@
+//! Local source ports for outgoing connects (`--source-port-range`), for networks whose firewalls
+//! only let scan traffic out from an allowed port range. Ports are handed out round-robin so each
+//! one rests as long as possible before it is reused; when every port is taken, a connect waits
+//! for one to be freed instead of failing.
+use anyhow::{bail, Context, Result};
+use std::collections::VecDeque;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::ops::RangeInclusive;
+use std::sync::{Arc, Mutex};
+use tokio::net::{TcpSocket, TcpStream};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// A shared pool of local ports; clones hand out ports from the same pool.
+#[derive(Clone, Debug)]
+pub struct SourcePorts {
+    range: RangeInclusive<u16>,
+    inner: Arc<Pool>,
+}
+
+#[derive(Debug)]
+struct Pool {
+    /// One permit per port in `free`, so waiting for a permit is waiting for a port.
+    permits: Arc<Semaphore>,
+    free: Mutex<VecDeque<u16>>,
+}
+
+impl SourcePorts {
+    pub fn new(range: RangeInclusive<u16>) -> Self {
+        let free: VecDeque<u16> = range.clone().collect();
+        Self {
+            inner: Arc::new(Pool {
+                permits: Arc::new(Semaphore::new(free.len())),
+                free: Mutex::new(free),
+            }),
+            range,
+        }
+    }
+
+    pub fn range(&self) -> RangeInclusive<u16> {
+        self.range.clone()
+    }
+
+    /// Take the next free port, waiting until one is released if all are in use.
+    pub async fn lease(&self) -> Lease {
+        let permit = Arc::clone(&self.inner.permits)
+            .acquire_owned()
+            .await
+            .expect("source port pool is never closed");
+        let port = self.pop();
+        Lease {
+            pool: self.clone(),
+            port,
+            _permit: permit,
+        }
+    }
+
+    /// Connect to `addr` from a leased port; the port goes back to the pool once connected.
+    pub async fn connect(&self, addr: SocketAddr) -> io::Result<TcpStream> {
+        self.lease().await.connect(addr).await
+    }
+
+    fn pop(&self) -> u16 {
+        self.inner
+            .free
+            .lock()
+            .expect("source port pool poisoned")
+            .pop_front()
+            .expect("a permit always has a free port")
+    }
+
+    /// Queue `busy` and take the port at the front, under one lock. With every other port leased
+    /// the queue is empty until `busy` goes in, so this hands `busy` straight back.
+    fn swap(&self, busy: u16) -> u16 {
+        let mut free = self.inner.free.lock().expect("source port pool poisoned");
+        free.push_back(busy);
+        free.pop_front().expect("the busy port was just queued")
+    }
+
+    fn push(&self, port: u16) {
+        self.inner
+            .free
+            .lock()
+            .expect("source port pool poisoned")
+            .push_back(port);
+    }
+}
+
+/// A port taken from [`SourcePorts`]; it returns to the back of the queue when dropped.
+#[derive(Debug)]
+pub struct Lease {
+    pool: SourcePorts,
+    port: u16,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Lease {
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Bind to the leased port and connect to `addr`. A port that is still busy (held by another
+    /// process, or already connected to this same `addr`) is swapped for the next free one; the
+    /// error is returned only once every port in the queue has been tried.
+    pub async fn connect(&mut self, addr: SocketAddr) -> io::Result<TcpStream> {
+        let mut tries = self.pool.range.len();
+        loop {
+            match bind_and_connect(self.port, addr).await {
+                Err(e) if is_busy(&e) && tries > 1 => {
+                    tries -= 1;
+                    self.port = self.pool.swap(self.port);
+                }
+                done => return done,
+            }
+        }
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        self.pool.push(self.port);
+    }
+}
+
+async fn bind_and_connect(port: u16, addr: SocketAddr) -> io::Result<TcpStream> {
+    let (socket, any) = match addr {
+        SocketAddr::V4(_) => (TcpSocket::new_v4()?, IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+        SocketAddr::V6(_) => (TcpSocket::new_v6()?, IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+    };
+    // Lets a port be reused while an earlier connection from it sits in TIME_WAIT.
+    socket.set_reuseaddr(true)?;
+    socket.bind(SocketAddr::new(any, port))?;
+    socket.connect(addr).await
+}
+
+fn is_busy(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::AddrInUse | io::ErrorKind::AddrNotAvailable
+    )
+}
+
+/// Parse `--source-port-range`: `LOW-HIGH` (inclusive) or a single port.
+pub fn parse_port_range(s: &str) -> Result<RangeInclusive<u16>> {
+    let s = s.trim();
+    let (low, high) = s.split_once('-').unwrap_or((s, s));
+    let parse = |p: &str| {
+        p.trim()
+            .parse::<u16>()
+            .with_context(|| format!("invalid source port '{}' in '{s}'", p.trim()))
+    };
+    let (low, high) = (parse(low)?, parse(high)?);
+    if low == 0 || low > high {
+        bail!("invalid source port range '{s}': expected LOW-HIGH with 1 <= LOW <= HIGH");
+    }
+    Ok(low..=high)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn parses_ranges_and_single_ports() {
+        assert_eq!(parse_port_range("40000-50000").unwrap(), 40000..=50000);
+        assert_eq!(parse_port_range(" 443 ").unwrap(), 443..=443);
+        assert!(parse_port_range("50000-40000").is_err());
+        assert!(parse_port_range("0-10").is_err());
+        assert!(parse_port_range("1-70000").is_err());
+    }
+
+    #[tokio::test]
+    async fn leases_cycle_through_the_range_and_wait_when_exhausted() {
+        let ports = SourcePorts::new(1000..=1001);
+        let a = ports.lease().await;
+        let b = ports.lease().await;
+        assert_eq!((a.port(), b.port()), (1000, 1001));
+        // Both ports are out: the next lease waits until one comes back.
+        let waiting = tokio::time::timeout(Duration::from_millis(50), ports.lease()).await;
+        assert!(waiting.is_err());
+        drop(a);
+        assert_eq!(ports.lease().await.port(), 1000);
+        drop(b);
+        // Returned ports go to the back, so the longest-rested one comes next.
+        assert_eq!(ports.lease().await.port(), 1000);
+    }
+
+    #[tokio::test]
+    async fn connects_from_a_port_in_the_range() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        // A port the OS just handed out is free to bind again once released.
+        let free = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let ports = SourcePorts::new(free..=free);
+        let stream = ports.connect(addr).await.unwrap();
+        assert_eq!(stream.local_addr().unwrap().port(), free);
+        let (accepted, peer) = listener.accept().await.unwrap();
+        assert_eq!(peer.port(), free);
+        drop(accepted);
+    }
+
+    #[tokio::test]
+    async fn busy_port_with_every_other_port_leased_fails_cleanly() {
+        let target = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = target.local_addr().unwrap();
+        // The first port of the range is held by a listener; the second is leased out.
+        let busy = std::net::TcpListener::bind("0.0.0.0:0").unwrap();
+        let port = busy.local_addr().unwrap().port();
+        let ports = SourcePorts::new(port..=port + 1);
+        let mut lease = ports.lease().await;
+        let other = ports.lease().await;
+        assert_eq!(lease.port(), port);
+        // The queue is empty while the busy port is swapped: this must error, not panic.
+        let err = lease.connect(addr).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+        drop(lease);
+        drop(other);
+        assert_eq!(ports.inner.free.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,137 @@
+use crate::types::ScanEntry;
+use ::time::{format_description::well_known, OffsetDateTime};
+use anyhow::{bail, Result};
+use tokio::net::UdpSocket;
+
+/// Facility `user` (1), severity `notice` (5).
+const PRI: u8 = 8 + 5;
+/// SD-ID for finding fields; 32473 is the IANA enterprise number reserved for examples.
+const SD_ID: &str = "lanscan@32473";
+const APP_NAME: &str = "lan-scan-rs";
+/// Default syslog Unix socket on Linux/BSD.
+pub const LOCAL_SOCKET: &str = "/dev/log";
+
+/// Where syslog messages go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyslogTarget {
+    /// Remote collector over UDP (`host:port`).
+    Udp(String),
+    /// Local daemon via the `/dev/log` datagram socket.
+    Local,
+}
+
+enum Transport {
+    Udp(UdpSocket),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixDatagram),
+}
+
+/// Sends open-port findings as RFC 5424 messages.
+pub struct SyslogSender {
+    transport: Transport,
+    hostname: String,
+}
+
+impl SyslogSender {
+    pub async fn connect(target: &SyslogTarget) -> Result<Self> {
+        let transport = match target {
+            SyslogTarget::Udp(addr) => {
+                let sock = UdpSocket::bind("0.0.0.0:0").await?;
+                sock.connect(addr).await?;
+                Transport::Udp(sock)
+            }
+            #[cfg(unix)]
+            SyslogTarget::Local => {
+                let sock = tokio::net::UnixDatagram::unbound()?;
+                sock.connect(LOCAL_SOCKET)?;
+                Transport::Unix(sock)
+            }
+            #[cfg(not(unix))]
+            SyslogTarget::Local => bail!("local syslog socket is only supported on Unix"),
+        };
+        Ok(Self {
+            transport,
+            hostname: local_hostname(),
+        })
+    }
+
+    pub async fn send_entry(&self, entry: &ScanEntry) -> Result<()> {
+        let msg = format_rfc5424(entry, &self.hostname, std::process::id());
+        let sent = match &self.transport {
+            Transport::Udp(s) => s.send(msg.as_bytes()).await?,
+            #[cfg(unix)]
+            Transport::Unix(s) => s.send(msg.as_bytes()).await?,
+        };
+        if sent != msg.len() {
+            bail!("short syslog write ({sent} of {} bytes)", msg.len());
+        }
+        Ok(())
+    }
+}
+
+/// Format one finding as an RFC 5424 message with ip/port/service structured data.
+pub fn format_rfc5424(entry: &ScanEntry, hostname: &str, pid: u32) -> String {
+    let ts = OffsetDateTime::now_utc()
+        .format(&well_known::Rfc3339)
+        .unwrap_or_else(|_| "-".to_string());
+    let service = entry.service.as_deref().unwrap_or("unknown");
+    let mut sd = format!(
+        "[{SD_ID} ip=\"{}\" port=\"{}\" service=\"{}\"",
+        sd_escape(&entry.ip),
+        entry.port,
+        sd_escape(service)
+    );
+    sd.push(']');
+    let mut msg = format!("open {}:{} {}", entry.ip, entry.port, service);
+    if let Some(b) = &entry.banner {
+        msg.push_str(" banner=");
+        msg.push_str(b);
+    }
+    format!("<{PRI}>1 {ts} {hostname} {APP_NAME} {pid} finding {sd} {msg}")
+}
+
+/// Escape `"`, `\` and `]` inside SD-PARAM values (RFC 5424 section 6.3.3).
+fn sd_escape(v: &str) -> String {
+    let mut out = String::with_capacity(v.len());
+    for c in v.chars() {
+        if matches!(c, '"' | '\\' | ']') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn local_hostname() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty() && !h.contains(' '))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_structured_data() {
+        let entry = ScanEntry {
+            latency_ms: 3,
+            service: Some("ssh".into()),
+            banner: Some("SSH-2.0-OpenSSH_9.8".into()),
+            ..ScanEntry::test_open("10.0.0.5", 22)
+        };
+        let msg = format_rfc5424(&entry, "scanner01", 42);
+        assert!(msg.starts_with("<13>1 "));
+        assert!(msg.contains(" scanner01 lan-scan-rs 42 finding "));
+        assert!(msg.contains("[lanscan@32473 ip=\"10.0.0.5\" port=\"22\" service=\"ssh\"]"));
+        assert!(msg.ends_with("open 10.0.0.5:22 ssh banner=SSH-2.0-OpenSSH_9.8"));
+    }
+
+    #[test]
+    fn escapes_sd_values() {
+        assert_eq!(sd_escape(r#"a"b\c]"#), r#"a\"b\\c\]"#);
+    }
+}
@@ -0,0 +1,366 @@
+//! Target spec parsing for the `lan-scan-rs` binary: `--targets` lists and files, and stdin.
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufRead};
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use lan_scan_rs::netdetect::{self, TargetSet};
+use lan_scan_rs::resolve::Resolver;
+use tokio::runtime::Handle;
+use tokio::sync::{mpsc, oneshot};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
+
+/// Targets parsed from `--targets`, with per-host metadata.
+#[derive(Debug, Default)]
+pub struct ParsedTargets {
+    pub ips: TargetSet,
+    /// Every hostname a target was given by, in order. Several names can share an IP (vhosts);
+    /// each is probed as its own target.
+    pub names: HashMap<IpAddr, Vec<String>>,
+    /// Trailing `# comment` from the targets file, applied to every host on that line.
+    pub labels: HashMap<IpAddr, String>,
+}
+
+/// Whether targets come from stdin (`--stdin` or `--targets -`).
+pub fn is_stdin_spec(stdin_flag: bool, targets: Option<&str>) -> bool {
+    stdin_flag || targets.map(str::trim) == Some("-")
+}
+
+/// A hostname seen while parsing, with the label of its line; resolved once parsing is done.
+struct PendingName {
+    name: String,
+    label: Option<String>,
+}
+
+/// Parse `--targets` (inline list or file) into IPs, hostnames, and file labels. Hostnames are
+/// resolved together through `resolver` once the whole list is read.
+pub async fn parse_targets_arg(
+    input: Option<&str>,
+    min_prefix_len: Option<u8>,
+    resolver: &Resolver,
+) -> Result<ParsedTargets> {
+    let mut parsed = ParsedTargets::default();
+    let mut pending = Vec::new();
+    if let Some(s) = input {
+        let s_trim = s.trim();
+        if !s_trim.is_empty() {
+            let p = Path::new(s_trim);
+            if p.is_file() {
+                let content = fs::read_to_string(p)
+                    .with_context(|| format!("failed to read targets file: {s_trim}"))?;
+                for line in content.lines() {
+                    parse_target_line(line, min_prefix_len, &mut parsed, &mut pending)?;
+                }
+                if parsed.ips.is_empty() && pending.is_empty() {
+                    eprintln!("Warning: targets file {s_trim} lists no targets");
+                }
+            } else if looks_like_path(s_trim) {
+                bail!(
+                    "targets file not found: {s_trim} (pass an existing file, or IPs, CIDRs, \
+                     and hostnames separated by commas)"
+                );
+            } else {
+                // Single token (IP or CIDR), or comma-separated list
+                for tok in s_trim.split(|c: char| c.is_whitespace() || c == ',') {
+                    let tok = tok.trim();
+                    if tok.is_empty() {
+                        continue;
+                    }
+                    push_target_token(tok, None, min_prefix_len, &mut parsed, &mut pending)?;
+                }
+            }
+        }
+    }
+    let names: Vec<String> = pending.iter().map(|p| p.name.clone()).collect();
+    let answers = resolver.resolve_all(&names).await;
+    for (p, ip) in pending.into_iter().zip(answers) {
+        add_named_target(&mut parsed, p.name, ip?, p.label);
+    }
+    Ok(parsed)
+}
+
+/// Read `--pairs`: `ip:port` entries (`[v6]:port` for IPv6) separated by commas, whitespace, or
+/// newlines, with `#` comments. Duplicates are dropped, keeping the first occurrence's order.
+pub fn load_pairs_file(path: &Path) -> Result<Vec<(IpAddr, u16)>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("failed to read pairs file: {}", path.display()))?;
+    parse_pairs(&content)
+}
+
+fn parse_pairs(s: &str) -> Result<Vec<(IpAddr, u16)>> {
+    let mut seen = HashSet::new();
+    let mut pairs = Vec::new();
+    for (idx, raw_line) in s.lines().enumerate() {
+        let line = raw_line.split('#').next().unwrap_or("");
+        for tok in line.split(|c: char| c.is_whitespace() || c == ',') {
+            if tok.is_empty() {
+                continue;
+            }
+            let addr: SocketAddr = tok
+                .parse()
+                .with_context(|| format!("line {}: expected ip:port, got {tok}", idx + 1))?;
+            if addr.port() == 0 {
+                bail!("line {}: port out of range: {tok}", idx + 1);
+            }
+            if seen.insert(addr) {
+                pairs.push((addr.ip(), addr.port()));
+            }
+        }
+    }
+    Ok(pairs)
+}
+
+/// Whether a `--targets` value that isn't an existing file was meant as one: `./hosts`,
+/// `lists/lab.txt`, `~/targets`. A CIDR such as `10.0.0.0/33` still reaches the CIDR parser so
+/// its own error is reported.
+fn looks_like_path(spec: &str) -> bool {
+    if spec.contains([',', ' ', '\t']) {
+        return false;
+    }
+    let after_slash = spec.rsplit_once('/').map(|(_, tail)| tail);
+    spec.starts_with(['.', '/', '~'])
+        || spec.contains('\\')
+        || after_slash.is_some_and(|tail| tail.parse::<u8>().is_err())
+        || [".txt", ".lst", ".list", ".csv"]
+            .iter()
+            .any(|ext| spec.ends_with(ext))
+}
+
+/// Parse one targets-file line (`spec[, spec...] [# label]`) into `parsed`. Blank and
+/// comment-only lines add nothing; a trailing comment labels every host the line adds.
+/// Hostnames go to `pending` for the caller to resolve.
+fn parse_target_line(
+    line: &str,
+    min_prefix_len: Option<u8>,
+    parsed: &mut ParsedTargets,
+    pending: &mut Vec<PendingName>,
+) -> Result<()> {
+    let (ln, comment) = line.split_once('#').unwrap_or((line, ""));
+    let ln = ln.trim();
+    if ln.is_empty() {
+        return Ok(());
+    }
+    let label = Some(comment.trim()).filter(|c| !c.is_empty());
+    for tok in ln.split(|c: char| c.is_whitespace() || c == ',') {
+        let tok = tok.trim();
+        if tok.is_empty() {
+            continue;
+        }
+        push_target_token(tok, label, min_prefix_len, parsed, pending)?;
+    }
+    Ok(())
+}
+
+/// Parse target lines from `reader` until EOF, resolving hostnames with `resolve` and calling
+/// `on_new_ip` for each address the first time it is seen. Bad lines and unresolvable names are
+/// reported and skipped so one typo doesn't end a pipeline; reading stops early if `on_new_ip`
+/// returns `false`.
+fn read_target_lines(
+    reader: impl BufRead,
+    min_prefix_len: Option<u8>,
+    mut resolve: impl FnMut(&str) -> Result<IpAddr>,
+    mut on_new_ip: impl FnMut(IpAddr) -> bool,
+) -> ParsedTargets {
+    let mut all = ParsedTargets::default();
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let mut parsed = ParsedTargets::default();
+        let mut pending = Vec::new();
+        if let Err(e) = parse_target_line(&line, min_prefix_len, &mut parsed, &mut pending) {
+            eprintln!("Warning: skipping target line {:?}: {e:#}", line.trim());
+            continue;
+        }
+        for p in pending {
+            match resolve(&p.name) {
+                Ok(ip) => add_named_target(&mut parsed, p.name, ip, p.label),
+                Err(e) => eprintln!("Warning: skipping target {}: {e:#}", p.name),
+            }
+        }
+        for (ip, names) in parsed.names {
+            let known = all.names.entry(ip).or_default();
+            for name in names {
+                if !known.contains(&name) {
+                    known.push(name);
+                }
+            }
+        }
+        for (ip, label) in parsed.labels {
+            all.labels.entry(ip).or_insert(label);
+        }
+        for ip in parsed.ips.hosts() {
+            if !all.ips.contains(ip) {
+                all.ips.push_host(ip);
+                if !on_new_ip(ip) {
+                    return all;
+                }
+            }
+        }
+    }
+    all
+}
+
+/// Read every target from stdin up front (used by --dry-run, which needs the full list).
+pub async fn read_stdin_targets(min_prefix_len: Option<u8>, resolver: Resolver) -> ParsedTargets {
+    let (stream, read) = stream_stdin_targets(min_prefix_len, resolver);
+    stream.collect::<Vec<_>>().await;
+    read.await.unwrap_or_default()
+}
+
+/// Stream targets from stdin as lines arrive. The returned receiver resolves to everything read
+/// (names and labels included) once stdin hits EOF or the scan stops listening.
+pub fn stream_stdin_targets(
+    min_prefix_len: Option<u8>,
+    resolver: Resolver,
+) -> (ReceiverStream<IpAddr>, oneshot::Receiver<ParsedTargets>) {
+    let (tx, rx) = mpsc::channel(1024);
+    let (done_tx, done_rx) = oneshot::channel();
+    let runtime = Handle::current();
+    // A plain thread rather than `spawn_blocking`: a read blocked on an idle pipe must not keep
+    // the runtime from shutting down after Ctrl+C. Hostnames resolve on the runtime from here.
+    std::thread::spawn(move || {
+        let all = read_target_lines(
+            io::stdin().lock(),
+            min_prefix_len,
+            |name| runtime.block_on(resolver.resolve(name)),
+            |ip| tx.blocking_send(ip).is_ok(),
+        );
+        let _ = done_tx.send(all);
+    });
+    (ReceiverStream::new(rx), done_rx)
+}
+
+/// Add one IP/CIDR token to `parsed` (with the line's label), or queue a hostname in `pending`.
+fn push_target_token(
+    tok: &str,
+    label: Option<&str>,
+    min_prefix_len: Option<u8>,
+    parsed: &mut ParsedTargets,
+    pending: &mut Vec<PendingName>,
+) -> Result<()> {
+    if tok.contains('/') || tok.parse::<IpAddr>().is_ok() {
+        parsed.ips.push_spec(tok, min_prefix_len)?;
+        if let Some(label) = label {
+            // Labels are kept per address, so only a labelled network is expanded here.
+            for ip in netdetect::expand_target(tok, min_prefix_len)? {
+                parsed.labels.entry(ip).or_insert_with(|| label.to_string());
+            }
+        }
+    } else {
+        pending.push(PendingName {
+            name: tok.to_string(),
+            label: label.map(str::to_string),
+        });
+    }
+    Ok(())
+}
+
+/// Record a resolved hostname target. A name is kept per target, so two vhosts on one IP both
+/// get probed under their own name.
+fn add_named_target(parsed: &mut ParsedTargets, name: String, ip: IpAddr, label: Option<String>) {
+    parsed.ips.push_host(ip);
+    if let Some(label) = label {
+        parsed.labels.entry(ip).or_insert(label);
+    }
+    let names = parsed.names.entry(ip).or_default();
+    if !names.contains(&name) {
+        names.push(name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn target_file_comments_become_labels() {
+        let path =
+            std::env::temp_dir().join(format!("lan-scan-targets-{}.txt", std::process::id()));
+        fs::write(
+            &path,
+            "# lab network\n10.0.0.1 # gateway\n10.0.1.0/30 # printers\n10.0.0.9\n",
+        )
+        .unwrap();
+        let parsed = parse_targets_arg(path.to_str(), None, &Resolver::default())
+            .await
+            .unwrap();
+        let _ = fs::remove_file(&path);
+        let label = |ip: &str| parsed.labels.get(&ip.parse().unwrap()).map(String::as_str);
+        assert_eq!(parsed.ips.len(), 4);
+        assert_eq!(label("10.0.0.1"), Some("gateway"));
+        assert_eq!(label("10.0.1.1"), Some("printers"));
+        assert_eq!(label("10.0.1.2"), Some("printers"));
+        assert_eq!(label("10.0.0.9"), None);
+    }
+
+    #[tokio::test]
+    async fn missing_targets_file_is_reported_as_such() {
+        let err = parse_targets_arg(Some("./no-such-targets.txt"), None, &Resolver::default())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("targets file not found"), "{err}");
+        assert!(looks_like_path("lists/lab") && looks_like_path("hosts.txt"));
+        assert!(!looks_like_path("10.0.0.0/33") && !looks_like_path("nas.local"));
+        assert!(!looks_like_path("10.0.0.1, 10.0.0.2"));
+    }
+
+    #[test]
+    fn pairs_parse_v4_v6_and_drop_duplicates() {
+        let pairs = parse_pairs(
+            "# re-check\n192.168.1.10:22, 192.168.1.20:443\n[fe80::1]:22 192.168.1.10:22\n",
+        )
+        .unwrap();
+        let shown: Vec<String> = pairs.iter().map(|(ip, p)| format!("{ip} {p}")).collect();
+        assert_eq!(shown, ["192.168.1.10 22", "192.168.1.20 443", "fe80::1 22"]);
+        let err = parse_pairs("10.0.0.1:22\n10.0.0.2\n").unwrap_err();
+        assert!(err.to_string().starts_with("line 2:"), "{err}");
+    }
+
+    #[test]
+    fn piped_lines_skip_blanks_comments_and_bad_specs() {
+        let input = "\n# header\n10.0.0.1\n10.0.0.0/33\n10.0.0.0/30 # lab\n10.0.0.1\n";
+        let mut streamed = Vec::new();
+        let no_dns = |name: &str| anyhow::bail!("unexpected lookup of {name}");
+        let parsed = read_target_lines(input.as_bytes(), None, no_dns, |ip| {
+            streamed.push(ip.to_string());
+            true
+        });
+        assert_eq!(streamed, ["10.0.0.1", "10.0.0.2"]);
+        assert_eq!(parsed.ips.len(), 2);
+        assert_eq!(
+            parsed
+                .labels
+                .get(&"10.0.0.2".parse().unwrap())
+                .map(String::as_str),
+            Some("lab")
+        );
+    }
+
+    #[tokio::test]
+    async fn hostnames_resolve_off_the_runtime_preferring_ipv4() {
+        let parsed = parse_targets_arg(Some("localhost"), None, &Resolver::default())
+            .await
+            .unwrap();
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        assert_eq!(parsed.ips.hosts().collect::<Vec<_>>(), [ip]);
+        assert_eq!(parsed.names[&ip], ["localhost"]);
+        assert!(
+            parse_targets_arg(Some("no-such-host.invalid"), None, &Resolver::default())
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn vhosts_on_one_ip_keep_every_name() {
+        let input = "app.test\nadmin.test # admin vhost\napp.test\n";
+        let vhost_ip: IpAddr = "10.0.0.5".parse().unwrap();
+        let parsed = read_target_lines(input.as_bytes(), None, |_| Ok(vhost_ip), |_| true);
+        assert_eq!(parsed.ips.hosts().collect::<Vec<_>>(), [vhost_ip]);
+        assert_eq!(parsed.names[&vhost_ip], ["app.test", "admin.test"]);
+    }
+}
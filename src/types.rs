@@ -1,22 +1,491 @@
+use crate::risk::Severity;
+use anyhow::bail;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use time::format_description::well_known::Rfc3339;
+use time::{OffsetDateTime, UtcOffset};
+
+/// Version of the JSON results shape; bump whenever `ScanResults` or `ScanEntry` changes and
+/// add a step to `schema::migrate`.
+pub const SCHEMA_VERSION: u32 = 16;
 
 /// One discovered scan result entry for an IP:port.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
 pub struct ScanEntry {
     pub ip: String,
     pub port: u16,
+    /// `false` only for refused (closed) ports recorded with `--note-refused`.
     pub open: bool,
     pub latency_ms: u64,
     pub service: Option<String>,
     pub banner: Option<String>,
+    /// When the port was recorded, in the scan's `TimestampFormat`.
     pub timestamp: String,
+    /// Label from the targets file (`192.168.1.1 # gateway`), if the host had one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// What the host advertised about this service during discovery (e.g. `mdns _ipp._tcp "Printer"`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub advertised: Option<String>,
+    /// Hostname the port was probed as (HTTP `Host`, TLS SNI) when the target was given by name;
+    /// vhosts sharing an IP get one entry each.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// Transport the port was found on; omitted for TCP.
+    #[serde(default, skip_serializing_if = "Protocol::is_tcp")]
+    pub protocol: Protocol,
+    /// How serious this open port is (see `risk::assess`); absent in files from older versions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+    /// With `--tls-verify`: whether the TLS certificate chain validated against the system
+    /// trust store; absent when not checked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_valid: Option<bool>,
+    /// A TLS handshake completed on this port; omitted when it didn't (plaintext services on
+    /// TLS ports included).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub tls: bool,
+    /// Protocol the server selected via TLS ALPN (`h2`, `http/1.1`, ...); absent when no
+    /// handshake completed or the server ignored ALPN.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alpn: Option<String>,
+    /// With `--baseline`: whether this open port was already open in the baseline.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<BaselineStatus>,
+}
+
+/// How an open entry compares with the baseline it was checked against (`diff::annotate`).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BaselineStatus {
+    New,
+    Unchanged,
+}
+
+impl BaselineStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BaselineStatus::New => "new",
+            BaselineStatus::Unchanged => "unchanged",
+        }
+    }
+}
+
+/// An open port as recorded in a baseline; also how `ScanResults::baseline_missing` lists the
+/// baseline ports that are no longer open.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct BaselinePort {
+    pub ip: String,
+    pub port: u16,
+    #[serde(default, skip_serializing_if = "Protocol::is_tcp")]
+    pub protocol: Protocol,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+}
+
+/// Transport of a scanned port. Only the SNMP check (`--snmp-communities`) reports UDP.
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    #[default]
+    Tcp,
+    Udp,
+}
+
+impl Protocol {
+    pub fn is_tcp(&self) -> bool {
+        *self == Protocol::Tcp
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Udp => "udp",
+        }
+    }
+}
+
+#[cfg(test)]
+impl ScanEntry {
+    /// An open entry with only the address set; tests fill in whatever else they check.
+    pub(crate) fn test_open(ip: &str, port: u16) -> Self {
+        Self {
+            ip: ip.into(),
+            port,
+            open: true,
+            latency_ms: 1,
+            service: None,
+            banner: None,
+            timestamp: String::new(),
+            label: None,
+            advertised: None,
+            host: None,
+            protocol: Protocol::Tcp,
+            severity: None,
+            tls_valid: None,
+            tls: false,
+            alpn: None,
+            baseline: None,
+        }
+    }
 }
 
 /// Aggregate results and progress counters.
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct ScanResults {
+    /// Schema version of this document (`SCHEMA_VERSION` when written by this build).
+    pub version: u32,
+    /// When the results were produced, in the scan's `TimestampFormat` (RFC 3339 UTC by default).
+    pub generated_at: String,
     pub scanned_total: u64,
     pub scanned_done: u64,
     pub open_count: u64,
+    /// TCP ports that refused the connection. Closed ports are only listed in `entries` (as
+    /// `open: false`) with `--note-refused`.
+    #[serde(default)]
+    pub closed_count: u64,
+    /// TCP ports where nothing answered (timeouts, unreachable errors); many of these usually
+    /// mean a firewall is dropping traffic.
+    #[serde(default)]
+    pub filtered_count: u64,
     pub entries: Vec<ScanEntry>,
+    /// Hosts that answered on any TCP port, open or refused, in address order. Unlike
+    /// `hosts_up`, this includes hosts whose scanned ports were all closed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub live_hosts: Vec<String>,
+    /// The scan was cancelled before finishing; `entries` holds what was found up to then.
+    #[serde(default)]
+    pub cancelled: bool,
+    /// The scan was stopped by its `max_duration` deadline (`cancelled` is then set too).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+    /// How the scan was run; set by the caller around the scan (absent in older files).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ScanMeta>,
+    /// Throughput and where the time went; absent in older files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<ScanTiming>,
+    /// With `--baseline`: ports open in the baseline that this scan did not find open.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub baseline_missing: Vec<BaselinePort>,
+}
+
+impl Default for ScanResults {
+    fn default() -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            generated_at: String::new(),
+            scanned_total: 0,
+            scanned_done: 0,
+            open_count: 0,
+            closed_count: 0,
+            filtered_count: 0,
+            entries: Vec::new(),
+            live_hosts: Vec::new(),
+            cancelled: false,
+            timed_out: false,
+            meta: None,
+            timing: None,
+            baseline_missing: Vec::new(),
+        }
+    }
+}
+
+impl ScanResults {
+    /// Order entries by address (numerically), port, protocol, then probed hostname. The sort
+    /// is stable and total, so repeated reads page through the same sequence.
+    pub fn sort_entries(&mut self) {
+        self.entries.sort_by(|a, b| {
+            let addr = |e: &ScanEntry| e.ip.parse::<IpAddr>().ok();
+            (addr(a), a.port, a.protocol, &a.host).cmp(&(addr(b), b.port, b.protocol, &b.host))
+        });
+    }
+
+    /// Distinct hosts with at least one open port. Addresses are compared parsed, so different
+    /// spellings of one IPv6 address (and IPv4-mapped IPv6) count once.
+    pub fn hosts_up(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| e.open)
+            .map(canonical_host)
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// Live hosts (see `live_hosts`) with no open TCP port. Hosts found only by a UDP probe
+    /// such as the SNMP sweep aren't in `live_hosts`, so they don't offset this count.
+    pub fn live_without_open(&self) -> usize {
+        let open: HashSet<String> = self
+            .entries
+            .iter()
+            .filter(|e| e.open && e.protocol.is_tcp())
+            .map(canonical_host)
+            .collect();
+        self.live_hosts
+            .iter()
+            .filter(|ip| !open.contains(&canonical_ip(ip)))
+            .count()
+    }
+
+    /// Distinct open sockets (`ip:port` per protocol). Unlike `open_count`, a port probed under
+    /// several vhost names or found again by a retry counts once.
+    pub fn open_sockets(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| e.open)
+            .map(|e| (canonical_host(e), e.port, e.protocol))
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// Share of scanned sockets that were open, in `0.0..=1.0`; `None` before anything was scanned.
+    pub fn open_rate(&self) -> Option<f64> {
+        (self.scanned_done > 0).then(|| self.open_sockets() as f64 / self.scanned_done as f64)
+    }
+
+    /// Mean connect latency of the open entries; `None` when nothing was open.
+    pub fn avg_open_latency_ms(&self) -> Option<f64> {
+        let open: Vec<u64> = self
+            .entries
+            .iter()
+            .filter(|e| e.open)
+            .map(|e| e.latency_ms)
+            .collect();
+        (!open.is_empty()).then(|| open.iter().sum::<u64>() as f64 / open.len() as f64)
+    }
+}
+
+/// `e.ip` with IPv6 spellings (and IPv4-mapped IPv6) folded into one form.
+fn canonical_host(e: &ScanEntry) -> String {
+    canonical_ip(&e.ip)
+}
+
+fn canonical_ip(ip: &str) -> String {
+    match ip.parse::<IpAddr>() {
+        Ok(addr) => addr.to_canonical().to_string(),
+        Err(_) => ip.to_string(),
+    }
+}
+
+/// How fast a scan went and where its time was spent, for tuning concurrency and timeouts.
+/// Connect and probe times are summed over every task, so with many connects in flight they
+/// add up to more than `duration_ms`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ScanTiming {
+    /// TCP connect attempts, one per port plus one per extra hostname a host was probed as.
+    pub connects: u64,
+    /// Wall-clock time of the scan.
+    pub duration_ms: u64,
+    /// Time spent waiting on connects, open or not.
+    pub connect_ms: u64,
+    /// Time spent probing open ports (banners, TLS, protocol probes).
+    pub probe_ms: u64,
+}
+
+impl ScanTiming {
+    pub fn connects_per_sec(&self) -> Option<f64> {
+        (self.duration_ms > 0).then(|| self.connects as f64 * 1000.0 / self.duration_ms as f64)
+    }
+
+    /// Fold in a follow-up pass (a retry) that ran after this one.
+    pub fn add(&mut self, other: &ScanTiming) {
+        self.connects += other.connects;
+        self.duration_ms += other.duration_ms;
+        self.connect_ms += other.connect_ms;
+        self.probe_ms += other.probe_ms;
+    }
+}
+
+/// Parameters and timing of a scan, so archived results are self-describing.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ScanMeta {
+    pub tool_version: String,
+    /// Start and end times, written like the run's other timestamps (`Timestamps`).
+    pub started_at: String,
+    pub finished_at: String,
+    pub duration_ms: u64,
+    pub concurrency: usize,
+    pub timeout_ms: u64,
+    /// Targets as the user gave them (CIDR/IP/host list, file path, or "auto").
+    pub target_spec: String,
+    pub port_count: usize,
+}
+
+impl ScanMeta {
+    /// Metadata for a scan starting now; call `finish` once it returns.
+    pub fn begin(
+        target_spec: &str,
+        concurrency: usize,
+        timeout: Duration,
+        port_count: usize,
+        stamps: Timestamps,
+    ) -> Self {
+        Self {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at: stamps.now(),
+            finished_at: String::new(),
+            duration_ms: 0,
+            concurrency,
+            timeout_ms: timeout.as_millis() as u64,
+            target_spec: target_spec.to_string(),
+            port_count,
+        }
+    }
+
+    /// Record the end time and the measured scan duration.
+    pub fn finish(&mut self, elapsed: Duration, stamps: Timestamps) {
+        self.finished_at = stamps.now();
+        self.duration_ms = elapsed.as_millis() as u64;
+    }
+}
+
+/// The machine's UTC offset as read by `init_local_offset`.
+static LOCAL_OFFSET: OnceLock<Option<UtcOffset>> = OnceLock::new();
+
+/// Read the machine's UTC offset once, before any threads are started: on Unix `time` refuses to
+/// look it up from a multithreaded process (the environment could change under it), so
+/// `OffsetDateTime::now_local()` fails inside the tokio runtime. Returns `None` when the offset
+/// can't be determined. The offset is kept for the whole run, so a DST change mid-scan isn't
+/// picked up.
+pub fn init_local_offset() -> Option<UtcOffset> {
+    *LOCAL_OFFSET.get_or_init(|| UtcOffset::current_local_offset().ok())
+}
+
+/// Local time from `now_local()`, else at the offset `init_local_offset` read at startup.
+fn local_now() -> Option<OffsetDateTime> {
+    OffsetDateTime::now_local().ok().or_else(|| {
+        let offset = (*LOCAL_OFFSET.get()?)?;
+        Some(OffsetDateTime::now_utc().to_offset(offset))
+    })
+}
+
+/// A run's timestamp settings (`--timestamp-format`, `--local-time`), applied to everything it
+/// writes: entries, `generated_at`, `meta`, and a saved baseline's `created_at`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timestamps {
+    pub format: TimestampFormat,
+    /// At the machine's UTC offset instead of in UTC (RFC 3339 only).
+    pub local: bool,
+}
+
+impl Timestamps {
+    /// The current time in these settings.
+    pub fn now(self) -> String {
+        if self.local {
+            self.format.now_local()
+        } else {
+            self.format.now()
+        }
+    }
+}
+
+/// How timestamps are written. They stay JSON strings, so epoch millis
+/// read as e.g. `"1759409723101"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimestampFormat {
+    /// `2025-10-02T12:55:23.101204Z`
+    #[default]
+    Rfc3339,
+    /// Milliseconds since the Unix epoch.
+    EpochMillis,
+}
+
+impl FromStr for TimestampFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "rfc3339" => Ok(TimestampFormat::Rfc3339),
+            "epoch-millis" => Ok(TimestampFormat::EpochMillis),
+            other => bail!("unknown timestamp format: {other} (expected rfc3339 or epoch-millis)"),
+        }
+    }
+}
+
+impl TimestampFormat {
+    /// The current time in this format.
+    pub fn now(self) -> String {
+        self.format(OffsetDateTime::now_utc())
+    }
+
+    /// The current time at the machine's UTC offset (`2025-10-02T14:55:23.101204+02:00`), or in
+    /// UTC when the offset is unknown. Epoch millis are the same either way.
+    pub fn now_local(self) -> String {
+        self.format(local_now().unwrap_or_else(OffsetDateTime::now_utc))
+    }
+
+    fn format(self, at: OffsetDateTime) -> String {
+        match self {
+            TimestampFormat::Rfc3339 => at.format(&Rfc3339).unwrap_or_else(|e| {
+                // Only years outside 0000-9999 fail, i.e. a badly wrong clock. Say so once
+                // rather than pass the placeholder off as a real time.
+                static WARNED: AtomicBool = AtomicBool::new(false);
+                if !WARNED.swap(true, Ordering::Relaxed) {
+                    eprintln!(
+                        "Warning: could not format the time {at} as RFC 3339 ({e}); writing 1970-01-01T00:00:00Z instead"
+                    );
+                }
+                String::from("1970-01-01T00:00:00Z")
+            }),
+            TimestampFormat::EpochMillis => (at.unix_timestamp_nanos() / 1_000_000).to_string(),
+        }
+    }
+}
+
+/// The work a scan would perform, computed without opening any sockets (dry-run).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ScanPlan {
+    pub total_connects: u64,
+    pub host_count: u64,
+    pub port_count: u64,
+    /// First few distinct target hosts, in scan order.
+    pub sample: Vec<String>,
+}
+
+impl ScanPlan {
+    /// Maximum number of hosts listed in `sample`.
+    pub const SAMPLE_LEN: usize = 10;
+
+    pub fn new(targets: &[IpAddr], ports: &[u16]) -> Self {
+        let mut seen = HashSet::new();
+        let mut sample = Vec::new();
+        for ip in targets {
+            if seen.insert(*ip) && sample.len() < Self::SAMPLE_LEN {
+                sample.push(ip.to_string());
+            }
+        }
+        Self {
+            total_connects: targets.len() as u64 * ports.len() as u64,
+            host_count: seen.len() as u64,
+            port_count: ports.len() as u64,
+            sample,
+        }
+    }
+
+    /// Plan a scan of hosts known to be distinct (a `TargetSet`), sampling only the first few.
+    pub fn for_distinct(hosts: impl ExactSizeIterator<Item = IpAddr>, ports: &[u16]) -> Self {
+        let host_count = hosts.len() as u64;
+        Self {
+            total_connects: host_count * ports.len() as u64,
+            host_count,
+            port_count: ports.len() as u64,
+            sample: hosts
+                .take(Self::SAMPLE_LEN)
+                .map(|ip| ip.to_string())
+                .collect(),
+        }
+    }
 }
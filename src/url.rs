@@ -0,0 +1,77 @@
+//! Just enough URL handling for the plain HTTP(S) requests the scanner makes itself (webhooks,
+//! UPnP device descriptions).
+use anyhow::{bail, Context, Result};
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct UrlParts {
+    pub(crate) tls: bool,
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) path: String,
+}
+
+/// Minimal `http(s)://host[:port][/path]` parser.
+pub(crate) fn parse_url(url: &str) -> Result<UrlParts> {
+    let (tls, rest) = if let Some(r) = url.strip_prefix("https://") {
+        (true, r)
+    } else if let Some(r) = url.strip_prefix("http://") {
+        (false, r)
+    } else {
+        bail!("URL must start with http:// or https://: {url}");
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let default_port = if tls { 443 } else { 80 };
+    // Bracketed IPv6 literals carry colons of their own.
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(v6) => match v6.split_once(']') {
+            Some((h, rest)) => (h, rest.strip_prefix(':')),
+            None => bail!("invalid URL: {url}"),
+        },
+        None => match authority.split_once(':') {
+            Some((h, p)) => (h, Some(p)),
+            None => (authority, None),
+        },
+    };
+    let port = match port {
+        Some(p) => p
+            .parse()
+            .with_context(|| format!("invalid port in URL: {url}"))?,
+        None => default_port,
+    };
+    if host.is_empty() {
+        bail!("URL has no host: {url}");
+    }
+    Ok(UrlParts {
+        tls,
+        host: host.to_string(),
+        port,
+        path: path.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_http_and_https_urls() {
+        assert_eq!(
+            parse_url("https://hooks.example.com/services/T0/B0").unwrap(),
+            UrlParts {
+                tls: true,
+                host: "hooks.example.com".into(),
+                port: 443,
+                path: "/services/T0/B0".into()
+            }
+        );
+        let u = parse_url("http://10.0.0.5:8088").unwrap();
+        assert_eq!(
+            (u.host.as_str(), u.port, u.path.as_str()),
+            ("10.0.0.5", 8088, "/")
+        );
+        assert!(parse_url("ftp://x").is_err());
+    }
+}
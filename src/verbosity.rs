@@ -0,0 +1,38 @@
+//! How chatty the CLI is. `-q` keeps stdout to the results alone, `-v` adds per-finding progress
+//! and probe decisions. Set once at startup; the `status!`/`detail!` macros check it.
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+static LEVEL: OnceLock<Verbosity> = OnceLock::new();
+
+pub fn init(level: Verbosity) {
+    let _ = LEVEL.set(level);
+}
+
+pub fn level() -> Verbosity {
+    LEVEL.get().copied().unwrap_or(Verbosity::Normal)
+}
+
+/// `println!` for configuration, discovery, and status lines; silent with `-q`.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if $crate::verbosity::level() >= $crate::verbosity::Verbosity::Normal {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// `println!` for extra detail shown only with `-v`.
+macro_rules! detail {
+    ($($arg:tt)*) => {
+        if $crate::verbosity::level() >= $crate::verbosity::Verbosity::Verbose {
+            println!($($arg)*);
+        }
+    };
+}
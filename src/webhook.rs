@@ -0,0 +1,112 @@
+use crate::alert::AlertFormat;
+use crate::types::{ScanEntry, ScanResults};
+use crate::url::parse_url;
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::json;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time;
+use tokio_native_tls::{native_tls, TlsConnector};
+
+/// Default time allowed for the whole webhook request.
+pub const DEFAULT_WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where and how to deliver the completion notification.
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    pub url: String,
+    pub timeout: Duration,
+    pub format: AlertFormat,
+}
+
+/// JSON summary posted on completion: totals, plus new findings when a baseline was given.
+pub fn summary_payload(
+    results: &ScanResults,
+    new_findings: Option<&[ScanEntry]>,
+) -> serde_json::Value {
+    let mut body = json!({
+        "tool": "lan-scan-rs",
+        "scanned_total": results.scanned_total,
+        "scanned_done": results.scanned_done,
+        "open_count": results.open_count,
+    });
+    if let Some(new) = new_findings {
+        body["new_findings"] = json!(new);
+    }
+    body
+}
+
+/// POST `body` as JSON to `cfg.url`; errors on connect/timeout failures or a non-2xx status.
+pub async fn post_json(cfg: &WebhookConfig, body: &serde_json::Value) -> Result<u16> {
+    time::timeout(cfg.timeout, post_json_inner(&cfg.url, body))
+        .await
+        .map_err(|_| anyhow!("webhook timed out after {} ms", cfg.timeout.as_millis()))?
+}
+
+async fn post_json_inner(url: &str, body: &serde_json::Value) -> Result<u16> {
+    let target = parse_url(url)?;
+    let payload = serde_json::to_vec(body)?;
+    let mut req = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: lan-scan-rs\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        target.path,
+        target.host,
+        payload.len()
+    )
+    .into_bytes();
+    req.extend_from_slice(&payload);
+
+    let stream = TcpStream::connect((target.host.as_str(), target.port))
+        .await
+        .with_context(|| format!("webhook connect to {}:{} failed", target.host, target.port))?;
+    let status = if target.tls {
+        let cx = TlsConnector::from(native_tls::TlsConnector::new()?);
+        let tls = cx.connect(&target.host, stream).await?;
+        exchange(tls, &req).await?
+    } else {
+        exchange(stream, &req).await?
+    };
+    if !(200..300).contains(&status) {
+        bail!("webhook returned HTTP {status}");
+    }
+    Ok(status)
+}
+
+async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, req: &[u8]) -> Result<u16> {
+    stream.write_all(req).await?;
+    let mut buf = Vec::with_capacity(256);
+    let mut tmp = [0u8; 256];
+    // The status line is all we need.
+    while !buf.contains(&b'\n') && buf.len() < 4096 {
+        let n = stream.read(&mut tmp).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&tmp[..n]);
+    }
+    let line = String::from_utf8_lossy(&buf);
+    line.split_whitespace()
+        .nth(1)
+        .and_then(|c| c.parse().ok())
+        .ok_or_else(|| anyhow!("malformed webhook response"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_includes_new_findings_only_with_baseline() {
+        let results = ScanResults {
+            scanned_total: 4,
+            scanned_done: 4,
+            open_count: 1,
+            ..Default::default()
+        };
+        let p = summary_payload(&results, None);
+        assert_eq!(p["open_count"], 1);
+        assert!(p.get("new_findings").is_none());
+        let p = summary_payload(&results, Some(&[]));
+        assert_eq!(p["new_findings"], json!([]));
+    }
+}
@@ -1,5 +1,5 @@
 use ipnet::Ipv4Net;
-use lan_scan_rs::netdetect::{expand_cidr_to_ips, ipv4_to_default_cidr};
+use lan_scan_rs::netdetect::{expand_cidr_to_ips, expand_targets, ipv4_to_default_cidr};
 use std::net::Ipv4Addr;
 
 #[test]
@@ -24,3 +24,28 @@ fn expand_excludes_network_and_broadcast() {
         vec![Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2)]
     );
 }
+
+#[test]
+fn overlapping_specs_yield_each_ip_once() {
+    let ips = expand_targets(&["10.0.0.2", "10.0.0.0/29", "10.0.0.5", "10.0.0.2"], None).unwrap();
+    let expected: Vec<std::net::IpAddr> =
+        (1..=6).map(|o| Ipv4Addr::new(10, 0, 0, o).into()).collect();
+    // First-seen order: the explicit .2 comes before the rest of the /29.
+    let mut first_seen = vec![expected[1]];
+    first_seen.extend(expected.iter().copied().filter(|ip| *ip != expected[1]));
+    assert_eq!(ips, first_seen);
+    assert!(expand_targets(&["10.0.0.0/33"], None).is_err());
+}
+
+#[test]
+fn bad_and_oversized_specs_are_invalid_targets() {
+    use lan_scan_rs::error::ScanError;
+    let err = expand_targets(&["10.0.0.300"], None).unwrap_err();
+    assert!(matches!(err, ScanError::InvalidTarget { ref spec, .. } if spec == "10.0.0.300"));
+    let err = expand_targets(&["10.0.0.0/8"], Some(16)).unwrap_err();
+    assert!(matches!(err, ScanError::InvalidTarget { .. }));
+    assert!(matches!(
+        expand_targets::<&str>(&[], None),
+        Err(ScanError::NoTargets)
+    ));
+}
@@ -1,4 +1,8 @@
-use lan_scan_rs::ports::parse_ports_str;
+use lan_scan_rs::ports::{
+    check_ports_str, common_first, format_ranges, load_ports_or_default, parse_ports_list,
+    parse_ports_str, parse_ports_str_all, profile_preset, quick_ports, PortOrder, PortsFallback,
+    ScanProfile,
+};
 
 #[test]
 fn parse_single_and_ranges_and_comments() {
@@ -23,3 +27,77 @@ fn invalid_port_rejected() {
     let input = "0\n"; // invalid: out of range
     assert!(parse_ports_str(input).is_err());
 }
+
+#[test]
+fn format_ranges_collapses_runs_and_round_trips() {
+    let ports = [8001, 443, 22, 8000, 80, 8002, 443];
+    let list = format_ranges(&ports);
+    assert_eq!(list, "22,80,443,8000-8002");
+    assert_eq!(
+        parse_ports_list(&list).unwrap(),
+        [22, 80, 443, 8000, 8001, 8002]
+    );
+    assert_eq!(format_ranges(&[]), "");
+}
+
+#[test]
+fn check_report_collects_every_bad_line_and_counts_duplicates() {
+    let report = check_ports_str("22\nabc\n80-81\n81\n90-85\n22 # again\n");
+    assert_eq!(report.ports, [22, 80, 81]);
+    assert_eq!(report.duplicates, 2);
+    let lines: Vec<usize> = report.errors.iter().map(|e| e.line).collect();
+    assert_eq!(lines, [2, 5]);
+    assert!(report.errors[0]
+        .to_string()
+        .starts_with("line 2: invalid port value: abc"));
+    assert!(report.errors[1].message.starts_with("invalid range 90-85"));
+}
+
+#[test]
+fn lenient_parse_returns_all_errors_or_the_ports() {
+    let errors = parse_ports_str_all("x\n22\n70000\n").unwrap_err();
+    assert_eq!(errors.iter().map(|e| e.line).collect::<Vec<_>>(), [1, 3]);
+    assert_eq!(parse_ports_str_all("22\n80-81\n").unwrap(), [22, 80, 81]);
+    // The default parser still stops at the first bad line.
+    assert!(parse_ports_str("x\n22\n70000\n").is_err());
+}
+
+#[test]
+fn common_first_ranks_known_ports_and_keeps_the_rest_in_order() {
+    assert_eq!(
+        common_first(&[9999, 22, 80, 1234, 443]),
+        [80, 443, 22, 9999, 1234]
+    );
+    assert_eq!(
+        "Common-First".parse::<PortOrder>().unwrap(),
+        PortOrder::CommonFirst
+    );
+    assert!("fastest".parse::<PortOrder>().is_err());
+}
+
+#[test]
+fn ports_fallback_applies_only_to_missing_files() {
+    let missing = std::path::Path::new("no-such-ports-file.txt");
+    assert_eq!(
+        load_ports_or_default(missing, PortsFallback::Quick).unwrap(),
+        quick_ports()
+    );
+    assert!(load_ports_or_default(missing, PortsFallback::None).is_err());
+    assert_eq!(
+        "none".parse::<PortsFallback>().unwrap(),
+        PortsFallback::None
+    );
+    assert!("later".parse::<PortsFallback>().is_err());
+}
+
+#[test]
+fn profiles_pick_ports_and_probes() {
+    let (ports, probes) = profile_preset("db".parse::<ScanProfile>().unwrap());
+    assert!(ports.contains(&5432) && ports.contains(&6379));
+    assert!(probes.redis && !probes.smb);
+    let (ports, probes) = profile_preset(ScanProfile::Web);
+    assert!(ports.contains(&443) && !ports.contains(&22));
+    assert_eq!(probes, Default::default());
+    assert_eq!(profile_preset(ScanProfile::Quick).0, quick_ports());
+    assert!("huge".parse::<ScanProfile>().is_err());
+}
@@ -0,0 +1,162 @@
+use lan_scan_rs::types::ScanPlan;
+use std::net::{IpAddr, Ipv4Addr};
+
+#[test]
+fn plan_counts_connects_and_distinct_hosts() {
+    let targets: Vec<IpAddr> = (1..=12)
+        .chain([1, 2])
+        .map(|o| IpAddr::V4(Ipv4Addr::new(10, 0, 0, o)))
+        .collect();
+    let plan = ScanPlan::new(&targets, &[22, 80, 443]);
+    assert_eq!(plan.total_connects, 14 * 3);
+    assert_eq!(plan.host_count, 12);
+    assert_eq!(plan.port_count, 3);
+    assert_eq!(plan.sample.len(), ScanPlan::SAMPLE_LEN);
+    assert_eq!(plan.sample[0], "10.0.0.1");
+}
+
+#[test]
+fn sample_output_matches_current_schema() {
+    let text = include_str!("../examples/sample-output.json");
+    let results = lan_scan_rs::schema::parse_results(text).unwrap();
+    assert_eq!(results.version, lan_scan_rs::types::SCHEMA_VERSION);
+    assert_eq!(results.entries.len() as u64, results.open_count);
+}
+
+#[test]
+fn entries_sort_numerically_by_address_then_port() {
+    let text = include_str!("../examples/sample-output.json");
+    let mut results = lan_scan_rs::schema::parse_results(text).unwrap();
+    let template = results.entries[0].clone();
+    results.entries = [("10.0.0.10", 22), ("10.0.0.9", 443), ("10.0.0.9", 22)]
+        .into_iter()
+        .map(|(ip, port)| {
+            let mut e = template.clone();
+            e.ip = ip.into();
+            e.port = port;
+            e
+        })
+        .collect();
+    results.sort_entries();
+    let order: Vec<String> = results
+        .entries
+        .iter()
+        .map(|e| format!("{}:{}", e.ip, e.port))
+        .collect();
+    assert_eq!(order, ["10.0.0.9:22", "10.0.0.9:443", "10.0.0.10:22"]);
+}
+
+#[test]
+fn hosts_up_dedups_addresses_across_spellings() {
+    let text = include_str!("../examples/sample-output.json");
+    let mut results = lan_scan_rs::schema::parse_results(text).unwrap();
+    let template = results.entries[0].clone();
+    results.entries = [
+        "10.0.0.1",
+        "10.0.0.1",
+        "::ffff:10.0.0.1",
+        "fe80::1",
+        "fe80:0::0001",
+    ]
+    .into_iter()
+    .map(|ip| {
+        let mut e = template.clone();
+        e.ip = ip.into();
+        e
+    })
+    .collect();
+    assert_eq!(results.hosts_up(), 2);
+    results.scanned_done = 20;
+    results.open_count = 5;
+    // Two distinct sockets (same port on two hosts), not the five entries.
+    assert_eq!(results.open_rate(), Some(0.1));
+    results.scanned_done = 0;
+    assert_eq!(results.open_rate(), None);
+}
+
+#[test]
+fn timestamp_formats_parse_and_render() {
+    use lan_scan_rs::types::TimestampFormat;
+    assert_eq!(
+        "epoch-millis".parse::<TimestampFormat>().unwrap(),
+        TimestampFormat::EpochMillis
+    );
+    assert!("iso".parse::<TimestampFormat>().is_err());
+    let millis: u64 = TimestampFormat::EpochMillis.now().parse().unwrap();
+    assert!(millis > 1_700_000_000_000);
+    let rfc = TimestampFormat::Rfc3339.now();
+    assert!(rfc.ends_with('Z') && rfc.contains('T'), "{rfc}");
+}
+
+#[test]
+fn local_timestamps_carry_their_offset() {
+    use lan_scan_rs::types::{init_local_offset, TimestampFormat};
+    let offset = init_local_offset();
+    let stamp = TimestampFormat::Rfc3339.now_local();
+    match offset {
+        Some(o) if !o.is_utc() => {
+            let tail = &stamp[stamp.len() - 6..];
+            assert!(
+                tail.starts_with(['+', '-']) && tail.as_bytes()[3] == b':',
+                "{stamp}"
+            );
+        }
+        // Unknown or zero offset: written as UTC.
+        _ => assert!(stamp.ends_with('Z') || stamp.ends_with("+00:00"), "{stamp}"),
+    }
+}
+
+#[test]
+fn open_rate_counts_each_open_socket_once() {
+    use lan_scan_rs::types::Protocol;
+    let text = include_str!("../examples/sample-output.json");
+    let mut results = lan_scan_rs::schema::parse_results(text).unwrap();
+    let template = results.entries[0].clone();
+    let entry = |ip: &str, port, host: Option<&str>, protocol| {
+        let mut e = template.clone();
+        e.ip = ip.into();
+        e.port = port;
+        e.host = host.map(Into::into);
+        e.protocol = protocol;
+        e
+    };
+    results.entries = vec![
+        // One socket probed under two vhost names.
+        entry("10.0.0.1", 443, Some("app.test"), Protocol::Tcp),
+        entry("10.0.0.1", 443, Some("admin.test"), Protocol::Tcp),
+        // Merged from snmp_sweep, which also counts its probe in scanned_done.
+        entry("10.0.0.1", 161, None, Protocol::Udp),
+        // Merged from a retry pass that found it again.
+        entry("10.0.0.2", 22, None, Protocol::Tcp),
+        entry("10.0.0.2", 22, None, Protocol::Tcp),
+    ];
+    results.open_count = 5;
+    results.scanned_done = 4 + 1;
+    assert_eq!(results.open_sockets(), 3);
+    assert_eq!(results.open_rate(), Some(3.0 / 5.0));
+}
+
+#[test]
+fn live_hosts_without_open_ports_ignore_snmp_only_hosts() {
+    use lan_scan_rs::types::Protocol;
+    let text = include_str!("../examples/sample-output.json");
+    let mut results = lan_scan_rs::schema::parse_results(text).unwrap();
+    let template = results.entries[0].clone();
+    let entry = |ip: &str, port, protocol| {
+        let mut e = template.clone();
+        e.ip = ip.into();
+        e.port = port;
+        e.protocol = protocol;
+        e
+    };
+    results.entries = vec![
+        entry("10.0.0.1", 22, Protocol::Tcp),
+        // Live, but only SNMP answered.
+        entry("10.0.0.2", 161, Protocol::Udp),
+        // Not live over TCP at all: only the SNMP sweep heard from it.
+        entry("10.0.0.9", 161, Protocol::Udp),
+    ];
+    results.live_hosts = vec!["10.0.0.1".into(), "10.0.0.2".into(), "10.0.0.3".into()];
+    assert_eq!(results.hosts_up(), 3);
+    assert_eq!(results.live_without_open(), 2);
+}
@@ -7,6 +7,7 @@
   const etaEl = $('#eta');
   const startBtn = $('#startBtn');
   const stopBtn = $('#stopBtn');
+  const pauseBtn = $('#pauseBtn');
   let pollTimer = null;
   let history = []; // [{t, scanned}]
 
@@ -66,9 +67,24 @@
     return res.json();
   }
 
-  function renderResults(results) {
+  // Results are fetched a page at a time and appended as each page arrives.
+  const PAGE_SIZE = 1000;
+
+  async function loadResults() {
     tableBody.innerHTML = '';
-    (results.entries || []).forEach(e => {
+    for (let offset = 0; ; offset += PAGE_SIZE) {
+      const res = await fetch(`/api/results?offset=${offset}&limit=${PAGE_SIZE}`);
+      if (!res.ok) throw new Error(`${res.status} ${res.statusText}`);
+      if (res.status === 204) return;
+      const page = await res.json();
+      appendResults(page.entries || []);
+      const total = parseInt(res.headers.get('X-Total-Count'), 10);
+      if (!(offset + PAGE_SIZE < total)) return;
+    }
+  }
+
+  function appendResults(entries) {
+    entries.forEach(e => {
       const tr = document.createElement('tr');
       const tdIp = document.createElement('td'); tdIp.textContent = e.ip;
       const tdPort = document.createElement('td'); tdPort.textContent = e.port;
@@ -88,8 +104,9 @@
     try {
       const s = await apiGet('/status');
       if (!s) return;
-      const pct = s.total > 0 ? Math.floor((s.scanned / s.total) * 100) : 0;
-      setStatus(`${s.state.toUpperCase()} — scanned ${s.scanned}/${s.total} (${pct}%), open: ${s.open}`);
+      const pct = s.total > 0 && !s.indeterminate ? Math.floor((s.scanned / s.total) * 100) : 0;
+      const of = s.indeterminate ? `${s.total} so far` : `${s.total} (${pct}%)`;
+      setStatus(`${s.state.toUpperCase()} — scanned ${s.scanned}/${of}, open: ${s.open}`);
       progressEl.style.width = pct + '%';
       progressEl.setAttribute('data-progress', pct + '%');
 
@@ -98,7 +115,9 @@
       history.push({ t: now, scanned: s.scanned });
       if (history.length > 10) history.shift();
       let eta = '—';
-      if (history.length >= 2) {
+      if (typeof s.eta_seconds === 'number') {
+        eta = humanEta(s.eta_seconds);
+      } else if (history.length >= 2 && !s.indeterminate) {
         const a = history[0];
         const b = history[history.length - 1];
         const dt = b.t - a.t;
@@ -112,18 +131,22 @@
       etaEl.textContent = eta;
 
       // Toggle buttons
-      if (s.state === 'running') {
+      if (s.state === 'running' || s.state === 'paused') {
         startBtn.disabled = true;
         stopBtn.disabled = false;
+        pauseBtn.disabled = false;
+        pauseBtn.textContent = s.state === 'paused' ? 'Resume' : 'Pause';
       } else {
         startBtn.disabled = false;
         stopBtn.disabled = true;
+        pauseBtn.disabled = true;
+        pauseBtn.textContent = 'Pause';
       }
+      if (s.state === 'paused') history = [];
       if (s.state === 'done') {
         clearInterval(pollTimer);
         pollTimer = null;
-        const r = await apiGet('/results');
-        if (r) renderResults(r);
+        await loadResults();
       }
     } catch (e) {
       // Soft-fail poll errors
@@ -168,9 +191,20 @@
     }
   }
 
+  async function togglePause() {
+    const resume = pauseBtn.textContent === 'Resume';
+    try {
+      await apiPost(resume ? '/resume' : '/pause', {});
+      pollLoop();
+    } catch (e) {
+      console.warn('pause/resume failed', e);
+    }
+  }
+
   document.addEventListener('DOMContentLoaded', () => {
     startBtn.addEventListener('click', startScan);
     stopBtn.addEventListener('click', stopScan);
+    pauseBtn.addEventListener('click', togglePause);
     // Start with an initial status fetch
     pollLoop();
   });